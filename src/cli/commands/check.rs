@@ -0,0 +1,54 @@
+use anyhow::Result;
+
+use crate::{
+    config::lint::{group_lints, lint_config, Lint},
+    fs::{
+        config::{get_default_config_path, read_config},
+        device::{find_input_devices, matches_device, DeviceInfo},
+    },
+};
+
+pub fn check(config: Option<String>) -> Result<()> {
+    let path = config.map_or_else(get_default_config_path, Ok)?;
+    let config = read_config(Some(path.clone()))?;
+    let mut lints = lint_config(&config);
+
+    match find_input_devices() {
+        Ok(devices) => {
+            let devices = devices
+                .iter()
+                .map(|(path, device)| DeviceInfo::new(path, device))
+                .collect::<Vec<_>>();
+
+            for (index, keyboard) in config.keyboards.iter().enumerate() {
+                if !devices.iter().any(|info| matches_device(keyboard, info)) {
+                    lints.push(Lint {
+                        keyboard: keyboard.label(index),
+                        message: "No matching device is currently connected".to_string(),
+                    });
+                }
+            }
+        }
+        Err(err) => println!("Failed to list the input devices: {err}\n"),
+    }
+
+    for (keyboard, messages) in group_lints(&lints) {
+        println!("• {keyboard}");
+
+        for (index, message) in messages.iter().enumerate() {
+            match index == messages.len() - 1 {
+                true => println!("  └─ {message}"),
+                false => println!("  ├─ {message}"),
+            }
+        }
+
+        println!();
+    }
+
+    match lints.len() {
+        0 => println!("{path}: no problems found"),
+        count => println!("{path}: {count} warning(s)"),
+    }
+
+    Ok(())
+}
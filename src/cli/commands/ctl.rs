@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use crate::daemon::{send_request, Request};
+
+pub fn send(request: Request) -> Result<()> {
+    let response = send_request(&request)?;
+
+    if let Some(error) = response.error {
+        return Err(anyhow!(error));
+    }
+
+    for keyboard in response.keyboards {
+        let layers = match keyboard.layers.is_empty() {
+            true => "none".to_string(),
+            false => keyboard.layers.join(" > "),
+        };
+
+        println!("• {}", keyboard.name);
+        println!("  ├─ Devices : {}", join_paths(&keyboard.devices));
+        println!("  ├─ Layers  : {layers}");
+        println!("  └─ Paused  : {}", keyboard.paused);
+        println!();
+    }
+
+    Ok(())
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,12 +1,15 @@
 use anyhow::Result;
 
-use crate::{cli::utils::device::is_keyboard, fs::device as fs};
+use crate::{
+    cli::utils::device::{is_keyboard, is_pointer},
+    fs::device as fs,
+};
 
-pub fn list(keyboard: bool) -> Result<()> {
+pub fn list(keyboard: bool, pointer: bool) -> Result<()> {
     let devices = fs::find_input_devices()?;
 
-    for device in devices {
-        if keyboard && !is_keyboard(&device) {
+    for (path, device) in devices {
+        if keyboard && !is_keyboard(&device) || pointer && !is_pointer(&device) {
             continue;
         }
 
@@ -16,7 +19,8 @@ pub fn list(keyboard: bool) -> Result<()> {
         let input_id = device.input_id();
 
         println!("• {name}");
-        println!("  ├─ Path      : {phys}");
+        println!("  ├─ Path      : {}", path.display());
+        println!("  ├─ Phys      : {phys}");
         println!("  ├─ Unique ID : {uniq}");
         println!("  ├─ Vendor    : {:#06x}", input_id.vendor());
         println!("  ├─ Product   : {:#06x}", input_id.product());
@@ -1,3 +1,7 @@
+pub mod check;
+pub mod ctl;
 pub mod device;
+pub mod schema;
 pub mod service;
 pub mod start;
+pub mod watch;
@@ -0,0 +1,8 @@
+use anyhow::Result;
+
+use crate::config::schema::config_schema;
+
+pub fn print_schema() -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&config_schema())?);
+    Ok(())
+}
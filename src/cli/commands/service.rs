@@ -33,6 +33,7 @@ Description=Okey Service
 
 [Service]
 ExecStart={exe_path_str} start --systemd
+ExecReload=/bin/kill -HUP $MAINPID
 Restart=on-failure
 StandardOutput=journal
 StandardError=journal
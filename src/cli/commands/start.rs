@@ -1,37 +1,25 @@
-use std::{fs::File, io, os::fd::AsRawFd, process, thread};
+use std::{fs::File, io, os::fd::AsRawFd, process};
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use nix::unistd::{self, ForkResult};
 
 use crate::{
-    core::{InputProxy, KeyAdapter},
-    fs::{config::read_config, device::find_device_by_name},
+    daemon::Daemon,
+    fs::config::{get_default_config_path, read_config_with_files},
 };
 
 pub fn start(config_path: Option<String>) -> Result<()> {
-    let parsed = read_config(config_path)?;
-
-    let handles = parsed.keyboards.into_iter().map(|keyboard| {
-        let defaults = parsed.defaults.clone();
-
-        thread::spawn(move || -> Result<()> {
-            let mut device = find_device_by_name(&keyboard.name)?
-                .ok_or(anyhow!("Device not found: {}", keyboard.name))?;
-
-            let mut proxy = InputProxy::try_from_device(&device)?;
-            let mut adapter = KeyAdapter::new(keyboard, defaults, &mut proxy);
-
-            adapter.hook(&mut device)
-        })
-    });
+    let config_path = match config_path {
+        Some(path) => path,
+        None => get_default_config_path()?,
+    };
 
+    // the configuration warnings are logged while reading it
     simple_logger::init()?;
 
-    for handle in handles {
-        handle.join().unwrap()?
-    }
+    let (parsed, files) = read_config_with_files(Some(config_path.clone()))?;
 
-    Ok(())
+    Daemon::new(parsed, config_path.into(), files).run()
 }
 
 pub fn start_daemon(config_path: Option<String>) -> Result<()> {
@@ -0,0 +1,51 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+use serde_json::json;
+
+use crate::{
+    cli::WatchFormat,
+    daemon::{subscribe, KeyboardState},
+};
+
+pub fn watch(format: WatchFormat, keyboard: Option<String>) -> Result<()> {
+    let mut stdout = io::stdout();
+
+    subscribe(|state| {
+        if keyboard.as_ref().is_none_or(|name| *name == state.name) {
+            writeln!(stdout, "{}", format_state(&state, format))?;
+            stdout.flush()?; // status bars read line by line from a pipe
+        }
+
+        Ok(())
+    })
+}
+
+pub fn format_state(state: &KeyboardState, format: WatchFormat) -> String {
+    let layer = state.layers.last().map(String::as_str).unwrap_or("base");
+
+    let layers = match state.layers.is_empty() {
+        true => "base".to_string(),
+        false => state.layers.join(" > "),
+    };
+
+    let paused = match state.paused {
+        true => " (paused)",
+        false => "",
+    };
+
+    match format {
+        WatchFormat::Json => json!(state).to_string(),
+        WatchFormat::Text => format!("{}: {layers}{paused}", state.name),
+        WatchFormat::Waybar => json!({
+            "text": layer,
+            "alt": layer,
+            "tooltip": format!("{}: {layers}{paused}", state.name),
+            "class": match state.paused {
+                true => "paused",
+                false => layer,
+            },
+        })
+        .to_string(),
+    }
+}
@@ -2,7 +2,9 @@ pub mod commands;
 
 mod utils;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+
+use crate::daemon::Request;
 
 #[derive(Parser, Debug)]
 #[command(version, about)]
@@ -15,7 +17,7 @@ pub struct Cli {
 pub enum Command {
     /// Start the keyboard remapping hook
     Start {
-        /// Configuration file path (default: ~/.config/okey/config.yaml)
+        /// Configuration file path (default: ~/.config/okey/config.{yaml,toml,json})
         #[arg(short, long)]
         config: Option<String>,
         /// Whether to start the process as a daemon
@@ -31,6 +33,16 @@ pub enum Command {
         systemd: bool,
     },
 
+    /// Validate a configuration file and report likely mistakes
+    Check {
+        /// Configuration file path (default: ~/.config/okey/config.{yaml,toml,json})
+        #[arg(short, long)]
+        config: Option<String>,
+    },
+
+    /// Print the JSON schema of the configuration file
+    Schema,
+
     /// Utility commands for the systemd service
     Service {
         #[command(subcommand)]
@@ -42,6 +54,32 @@ pub enum Command {
         #[command(subcommand)]
         command: DeviceSubcommand,
     },
+
+    /// Control the running okey instance
+    Ctl {
+        #[command(subcommand)]
+        command: CtlSubcommand,
+    },
+
+    /// Print the keyboard states of the running okey instance on each change
+    Watch {
+        /// Output format of each line
+        #[arg(short, long, value_enum, default_value_t = WatchFormat::Text)]
+        format: WatchFormat,
+        /// Only watch the given keyboard
+        #[arg(short, long)]
+        keyboard: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum WatchFormat {
+    /// The raw state object
+    Json,
+    /// A human readable summary
+    Text,
+    /// Custom module output for waybar (text, alt, tooltip and class)
+    Waybar,
 }
 
 #[derive(Parser, Debug)]
@@ -67,5 +105,74 @@ pub enum DeviceSubcommand {
         /// Whether to only show keyboards
         #[arg(short, long, default_value_t = false)]
         keyboard: bool,
+        /// Whether to only show pointer devices (mice, touchpads, trackpoints...)
+        #[arg(short, long, default_value_t = false)]
+        pointer: bool,
+    },
+}
+
+#[derive(Parser, Debug)]
+pub enum CtlSubcommand {
+    /// List the hooked keyboards along with their state
+    Keyboards,
+    /// Show the active layers
+    Layers {
+        /// Only show the given keyboard
+        #[arg(short, long)]
+        keyboard: Option<String>,
+    },
+    /// Activate a layer
+    Activate {
+        layer: String,
+        /// Only apply to the given keyboard
+        #[arg(short, long)]
+        keyboard: Option<String>,
+    },
+    /// Deactivate a layer
+    Deactivate {
+        layer: String,
+        /// Only apply to the given keyboard
+        #[arg(short, long)]
+        keyboard: Option<String>,
     },
+    /// Toggle a layer
+    Toggle {
+        layer: String,
+        /// Only apply to the given keyboard
+        #[arg(short, long)]
+        keyboard: Option<String>,
+    },
+    /// Pause remapping, keys are passed through unchanged
+    Pause {
+        /// Only apply to the given keyboard
+        #[arg(short, long)]
+        keyboard: Option<String>,
+    },
+    /// Resume remapping
+    Resume {
+        /// Only apply to the given keyboard
+        #[arg(short, long)]
+        keyboard: Option<String>,
+    },
+    /// Reload the configuration file
+    Reload,
+}
+
+impl From<CtlSubcommand> for Request {
+    fn from(value: CtlSubcommand) -> Self {
+        match value {
+            CtlSubcommand::Keyboards => Request::Keyboards,
+            CtlSubcommand::Layers { keyboard } => Request::Layers { keyboard },
+            CtlSubcommand::Activate { layer, keyboard } => {
+                Request::ActivateLayer { layer, keyboard }
+            }
+            CtlSubcommand::Deactivate { layer, keyboard } => {
+                Request::DeactivateLayer { layer, keyboard }
+            }
+            CtlSubcommand::Toggle { layer, keyboard } => Request::ToggleLayer { layer, keyboard },
+            CtlSubcommand::Pause { keyboard } => Request::Pause { keyboard },
+            CtlSubcommand::Resume { keyboard } => Request::Resume { keyboard },
+            CtlSubcommand::Reload => Request::Reload,
+        }
+    }
 }
@@ -1,7 +1,16 @@
-use evdev::{Device, KeyCode};
+use evdev::{Device, KeyCode, RelativeAxisCode};
 
 pub fn is_keyboard(device: &Device) -> bool {
     device
         .supported_keys()
         .is_some_and(|keys| keys.contains(KeyCode::KEY_A))
 }
+
+pub fn is_pointer(device: &Device) -> bool {
+    device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(KeyCode::BTN_LEFT))
+        || device
+            .supported_relative_axes()
+            .is_some_and(|axes| axes.contains(RelativeAxisCode::REL_X))
+}
@@ -6,6 +6,9 @@ mod constants {
     pub fn deferred_key_delay() -> u16 { 0 }
     pub fn unicode_input_delay() -> u16 { 50 }
     pub fn maximum_lookup_depth() -> u8 { 10 }
+    pub fn macro_repeat() -> u32 { 1 }
+    pub fn repeat_delay() -> u16 { 600 }
+    pub fn repeat_rate() -> u16 { 25 }
 }
 
 pub use constants::*;
@@ -0,0 +1,338 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use super::schema::{
+    known_keycodes, Config, EventMacro, KeyAction, KeyCode, KeyboardConfig, Macro, TemplateNames,
+};
+
+/// Custom keycodes using these prefixes don't need to be declared
+const CUSTOM_KEYCODE_PREFIXES: [&str; 2] = ["CUSTOM_", "LAYER_"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub keyboard: String,
+    pub message: String,
+}
+
+/// Reports configuration mistakes that are valid syntax-wise but most likely unintended
+pub fn lint_config(config: &Config) -> Vec<Lint> {
+    let profile_layers = config
+        .app_profiles
+        .iter()
+        .flat_map(|app_profiles| &app_profiles.profiles)
+        .flat_map(|profile| &profile.layers)
+        .collect::<HashSet<_>>();
+
+    let mut lints = config
+        .keyboards
+        .iter()
+        .enumerate()
+        .flat_map(|(index, keyboard)| {
+            let label = keyboard.label(index);
+
+            lint_keyboard(keyboard, &config.custom_keycodes, &profile_layers)
+                .into_iter()
+                .map(move |message| Lint {
+                    keyboard: label.clone(),
+                    message,
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let extended = config
+        .keyboards
+        .iter()
+        .chain(config.templates.values())
+        .filter_map(|keyboard| keyboard.extends.as_ref())
+        .flat_map(TemplateNames::names)
+        .collect::<HashSet<_>>();
+
+    let unused = config
+        .templates
+        .keys()
+        .filter(|name| !extended.contains(name))
+        .collect::<BTreeSet<_>>();
+
+    for name in unused {
+        lints.push(Lint {
+            keyboard: format!("template {name}"),
+            message: "Template is never extended".to_string(),
+        });
+    }
+
+    lints
+}
+
+/// Returns the undeclared custom keycodes, which are most likely typos
+pub fn lint_keycodes(config: &Config) -> Vec<Lint> {
+    config
+        .keyboards
+        .iter()
+        .enumerate()
+        .flat_map(|(index, keyboard)| {
+            let label = keyboard.label(index);
+
+            undeclared_keycodes(keyboard, &config.custom_keycodes)
+                .into_iter()
+                .map(move |message| Lint {
+                    keyboard: label.clone(),
+                    message,
+                })
+        })
+        .collect()
+}
+
+/// Groups the lint messages by keyboard, keeping the order in which the keyboards appear
+pub fn group_lints(lints: &[Lint]) -> Vec<(&str, Vec<&str>)> {
+    let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+
+    for lint in lints {
+        match groups
+            .iter_mut()
+            .find(|(keyboard, _)| *keyboard == lint.keyboard)
+        {
+            Some((_, messages)) => messages.push(&lint.message),
+            None => groups.push((&lint.keyboard, vec![&lint.message])),
+        }
+    }
+
+    groups
+}
+
+fn undeclared_keycodes(keyboard: &KeyboardConfig, declared: &[String]) -> Vec<String> {
+    let mut known = None;
+
+    keyboard_keycodes(keyboard)
+        .into_iter()
+        .filter(|code| code.is_custom())
+        .map(|code| code.to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|name| {
+            !declared.contains(name)
+                && !CUSTOM_KEYCODE_PREFIXES
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+        })
+        .map(|name| {
+            let known = known.get_or_insert_with(known_keycodes);
+
+            match suggest_keycode(&name, known) {
+                Some(suggestion) => format!(
+                    "Unknown keycode {name} (did you mean {suggestion}?), declare it in custom_keycodes if it is a custom keycode"
+                ),
+                None => format!(
+                    "Unknown keycode {name}, declare it in custom_keycodes if it is a custom keycode"
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Returns the closest known keycode within a third of the name length
+pub fn suggest_keycode<'a>(name: &str, known: &'a [String]) -> Option<&'a str> {
+    let name = name.to_uppercase();
+
+    // truncated or extended names are favored (e.g. KEY_ESC for KEY_ESCAPE)
+    known
+        .iter()
+        .map(|value| (edit_distance(&name, value), value))
+        .filter(|(distance, _)| *distance <= name.len() / 3)
+        .min_by_key(|(distance, value)| {
+            match name.starts_with(value.as_str()) || value.starts_with(&name) {
+                true => distance.saturating_sub(2),
+                false => *distance,
+            }
+        })
+        .map(|(_, value)| value.as_str())
+}
+
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right = right.chars().collect::<Vec<_>>();
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, right_char) in right.iter().enumerate() {
+            let cost = usize::from(left_char != *right_char);
+            let value = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+
+            current.push(value);
+        }
+
+        previous = current;
+    }
+
+    previous[right.len()]
+}
+
+fn lint_keyboard(
+    keyboard: &KeyboardConfig,
+    declared: &[String],
+    profile_layers: &HashSet<&String>,
+) -> Vec<String> {
+    let mut lints = Vec::new();
+
+    let layers = keyboard.layers.iter().collect::<BTreeMap<_, _>>();
+    let emitted = emitted_keycodes(keyboard);
+
+    let sources = keyboard
+        .keys
+        .keys()
+        .chain(keyboard.tap_dances.keys())
+        .chain(layers.values().flat_map(|layer| layer.keys.keys()))
+        .chain(layers.values().map(|layer| layer.modifier.get_modifer()))
+        .copied()
+        .collect::<HashSet<_>>();
+
+    lints.extend(undeclared_keycodes(keyboard, declared));
+
+    let unbound = emitted
+        .iter()
+        .filter(|code| code.is_custom() && !sources.contains(code))
+        .map(|code| code.to_string())
+        .collect::<BTreeSet<_>>();
+
+    for name in unbound {
+        lints.push(format!(
+            "Custom keycode {name} is never bound to a layer, a tap dance or a mapping"
+        ));
+    }
+
+    let mut modifiers = BTreeMap::<String, Vec<&String>>::new();
+
+    for (name, layer) in &layers {
+        let modifier = *layer.modifier.get_modifer();
+
+        modifiers
+            .entry(modifier.to_string())
+            .or_default()
+            .push(name);
+
+        // physical keys are intercepted by mappings and tap dances
+        let is_physical = !modifier.is_custom()
+            && !keyboard.keys.contains_key(&modifier)
+            && !keyboard.tap_dances.contains_key(&modifier);
+
+        if !is_physical
+            && !emitted.contains(&modifier)
+            && layer.activate_when.is_none()
+            && !profile_layers.contains(name)
+        {
+            lints.push(format!(
+                "Layer {name} is unreachable, its modifier {modifier} is never emitted"
+            ));
+        }
+    }
+
+    for (modifier, names) in modifiers {
+        if let [first, others @ ..] = names.as_slice() {
+            for name in others {
+                lints.push(format!(
+                    "Layers {first} and {name} share the modifier {modifier}, only one of them is used"
+                ));
+            }
+        }
+    }
+
+    let combos = &keyboard.combos.0;
+
+    for (index, combo) in combos.iter().enumerate() {
+        let keys = format_keys(&combo.keys);
+
+        for key in &combo.keys {
+            if keyboard.tap_dances.contains_key(key) {
+                lints.push(format!("Combo {keys} overlaps with the tap dance on {key}"));
+            }
+        }
+
+        let key_set = combo.keys.iter().collect::<HashSet<_>>();
+
+        if combos[..index]
+            .iter()
+            .any(|other| other.keys.iter().collect::<HashSet<_>>() == key_set)
+        {
+            lints.push(format!("Combo {keys} is defined more than once"));
+        }
+    }
+
+    lints
+}
+
+/// Returns every keycode used in the keyboard configuration
+fn keyboard_keycodes(keyboard: &KeyboardConfig) -> Vec<KeyCode> {
+    let repeat_keys = keyboard
+        .repeat
+        .iter()
+        .flat_map(|repeat| repeat.keys.keys().chain(&repeat.disabled_keys));
+
+    keyboard
+        .keys
+        .keys()
+        .chain(keyboard.tap_dances.keys())
+        .chain(keyboard.combos.0.iter().flat_map(|combo| &combo.keys))
+        .chain(keyboard.layers.values().flat_map(|layer| layer.keys.keys()))
+        .chain(
+            keyboard
+                .layers
+                .values()
+                .map(|layer| layer.modifier.get_modifer()),
+        )
+        .chain(keyboard.debounce.keys.keys())
+        .chain(repeat_keys)
+        .copied()
+        .chain(emitted_keycodes(keyboard))
+        .collect()
+}
+
+/// Returns the keycodes that can be emitted by an action of the keyboard
+fn emitted_keycodes(keyboard: &KeyboardConfig) -> HashSet<KeyCode> {
+    let layer_actions = keyboard.layers.values().flat_map(|layer| {
+        layer
+            .keys
+            .values()
+            .chain(&layer.on_enter)
+            .chain(&layer.on_exit)
+    });
+
+    keyboard
+        .keys
+        .values()
+        .chain(
+            keyboard
+                .tap_dances
+                .values()
+                .flat_map(|td| [&td.tap, &td.hold]),
+        )
+        .chain(keyboard.combos.0.iter().map(|combo| &combo.action))
+        .chain(layer_actions)
+        .flat_map(action_keycodes)
+        .collect()
+}
+
+fn action_keycodes(action: &KeyAction) -> Vec<KeyCode> {
+    let events = match action {
+        KeyAction::KeyCode(code) => return vec![*code],
+        KeyAction::Macro(Macro::Single(event)) => std::slice::from_ref(event),
+        KeyAction::Macro(Macro::Sequence(events)) => events.as_slice(),
+        KeyAction::Macro(Macro::Repeat { sequence, .. }) => sequence.as_slice(),
+    };
+
+    events
+        .iter()
+        .filter_map(|event| match event {
+            EventMacro::Tap(code)
+            | EventMacro::Press { press: code }
+            | EventMacro::Hold { hold: code }
+            | EventMacro::Release { release: code } => Some(*code),
+            _ => None,
+        })
+        .collect()
+}
+
+fn format_keys(keys: &[KeyCode]) -> String {
+    let keys = keys.iter().map(KeyCode::to_string).collect::<Vec<_>>();
+    format!("[{}]", keys.join(", "))
+}
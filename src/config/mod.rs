@@ -1,2 +1,3 @@
 pub mod defaults;
+pub mod lint;
 pub mod schema;
@@ -1,20 +1,111 @@
-use std::{cell::RefCell, collections::HashMap, hash::Hash, str::FromStr};
+use std::{
+    borrow::Cow, cell::RefCell, collections::HashMap, fmt, hash::Hash, path::PathBuf, slice,
+    str::FromStr,
+};
 
+use regex::Regex;
+use schemars::{
+    generate::SchemaSettings, json_schema, transform::transform_subschemas, JsonSchema, Schema,
+    SchemaGenerator,
+};
 use serde::{
-    de::{value::StringDeserializer, IntoDeserializer},
+    de::{self, value::StringDeserializer, IntoDeserializer},
     Deserialize, Deserializer,
 };
 
 use super::defaults;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct Config {
+    /// Configuration files merged into this one, relative to this file
+    #[serde(default)]
+    #[allow(dead_code)] // resolved when reading the configuration file
+    pub include: Vec<PathBuf>,
+    /// Reusable keyboard fragments, merged into the keyboards extending them
+    #[serde(default)]
+    pub templates: HashMap<String, KeyboardConfig>,
+    /// Shared global settings
     #[serde(default)]
     pub defaults: DefaultConfig,
+    /// Per keyboard configuration
     pub keyboards: Vec<KeyboardConfig>,
+    /// Layers activated depending on the focused application
+    pub app_profiles: Option<AppProfilesConfig>,
+    /// Access to the control socket
+    #[serde(default)]
+    pub control: ControlConfig,
+    /// Custom keycodes used for layers and macros, other unknown keycodes are reported as typos (not required for keycodes starting with `CUSTOM_` or `LAYER_`)
+    #[serde(default)]
+    pub custom_keycodes: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
+pub struct ControlConfig {
+    /// Group allowed to use the system socket, any user can use it by default
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct AppProfilesConfig {
+    /// Source of the focused window, detected from the environment by default
+    #[serde(default)]
+    pub provider: FocusProvider,
+    pub profiles: Vec<AppProfile>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum FocusProvider {
+    Builtin(FocusProviderKind),
+    /// Unix socket sending one window per line
+    Socket {
+        socket: PathBuf,
+    },
+    /// File containing the focused window, read on each change
+    File {
+        file: PathBuf,
+    },
+}
+
+impl Default for FocusProvider {
+    fn default() -> Self {
+        Self::Builtin(FocusProviderKind::Auto)
+    }
 }
 
-#[derive(Debug, Default, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusProviderKind {
+    #[default]
+    Auto,
+    Sway,
+    Hyprland,
+    X11,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct AppProfile {
+    /// Window class (or Wayland app_id)
+    pub class: Option<Pattern>,
+    /// Window title
+    pub title: Option<Pattern>,
+    /// Layers activated while a matching window is focused
+    pub layers: Vec<String>,
+}
+
+impl AppProfile {
+    pub fn matches(&self, class: &str, title: &str) -> bool {
+        self.class
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(class))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(title))
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
 pub struct DefaultConfig {
     #[serde(default)]
     pub tap_dance: DefaultTapDanceConfig,
@@ -24,8 +115,9 @@ pub struct DefaultConfig {
     pub general: GeneralConfig,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct DefaultTapDanceConfig {
+    /// Fallback tap dance timeout
     #[serde(default = "defaults::tap_dance_timeout")]
     pub default_timeout: u16,
 }
@@ -38,8 +130,9 @@ impl Default for DefaultTapDanceConfig {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct DefaultComboConfig {
+    /// Window for acknowledging combos
     #[serde(default = "defaults::combo_threshold")]
     pub default_threshold: u16,
 }
@@ -52,16 +145,28 @@ impl Default for DefaultComboConfig {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct GeneralConfig {
+    /// Main event loop interval
     #[serde(default = "defaults::event_poll_timeout")]
     pub event_poll_timeout: u16,
+    /// Delay for keys following non-acknowledged special keys
     #[serde(default = "defaults::deferred_key_delay")]
     pub deferred_key_delay: u16,
+    /// Delay for inserting unicode codepoints with macro
     #[serde(default = "defaults::unicode_input_delay")]
     pub unicode_input_delay: u16,
+    /// Maximum number of nested keycode lookups, stops mappings emitting each other endlessly
     #[serde(default = "defaults::maximum_lookup_depth")]
     pub maximum_lookup_depth: u8,
+    /// Keyboard layout used by the system for typing string macros
+    #[serde(default)]
+    pub layout: KeyboardLayout,
+    /// Method used for inserting unicode characters
+    #[serde(default)]
+    pub unicode_method: UnicodeMethod,
+    /// Key cancelling all running macros
+    pub macro_cancel_key: Option<KeyCode>,
 }
 
 impl Default for GeneralConfig {
@@ -71,51 +176,350 @@ impl Default for GeneralConfig {
             deferred_key_delay: defaults::deferred_key_delay(),
             unicode_input_delay: defaults::unicode_input_delay(),
             maximum_lookup_depth: defaults::maximum_lookup_depth(),
+            layout: KeyboardLayout::default(),
+            unicode_method: UnicodeMethod::default(),
+            macro_cancel_key: None,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyboardLayout {
+    #[default]
+    Us,
+    Fr,
+    De,
+    Dvorak,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum UnicodeMethod {
+    Simple(UnicodeMethodKind),
+    Compose {
+        /// Compose key used by the system
+        compose: KeyCode,
+    },
+    Clipboard {
+        /// Command writing its standard input to the clipboard
+        clipboard: String,
+        /// Shortcut used for pasting the clipboard content
+        #[serde(default)]
+        paste: PasteShortcut,
+    },
+    Command {
+        /// Command inserting the text from its standard input
+        command: String,
+    },
+}
+
+impl Default for UnicodeMethod {
+    fn default() -> Self {
+        Self::Simple(UnicodeMethodKind::Gtk)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeMethodKind {
+    Gtk,
+    Compose,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteShortcut {
+    #[default]
+    CtrlV,
+    CtrlShiftV,
+    ShiftInsert,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct KeyboardConfig {
+    /// Keyboard fragments merged into this keyboard, relative to the including file
+    #[serde(default)]
+    #[allow(dead_code)] // resolved when reading the configuration file
+    pub include: Vec<PathBuf>,
+    /// Templates merged into this keyboard, in order
+    pub extends: Option<TemplateNames>,
+    /// The name of the keyboard as an input device (use `okey device list --keyboard`)
+    #[serde(default)]
     pub name: String,
+    /// Device properties to match, every device matching all the given properties is hooked (use `okey device list --keyboard`)
+    #[serde(rename = "match")]
+    pub matcher: Option<DeviceMatchConfig>,
+    /// Whether to merge all the matching devices into a single keyboard
+    #[serde(default)]
+    pub merge: bool,
+    /// Whether to convert scroll wheel events into `WHEEL_*` keycodes, also allows keyboards to emit them
+    #[serde(default)]
+    pub wheel_keys: bool,
+    /// Key mappings for the main layer
     #[serde(default)]
     pub keys: HashMap<KeyCode, KeyAction>,
+    /// List of combo mappings
     #[serde(default)]
     pub combos: ComboConfig,
+    /// Dual function keys on tap/hold
     #[serde(default)]
     pub tap_dances: HashMap<KeyCode, TapDanceConfig>,
+    /// Virtual layers (shift-like)
     #[serde(default)]
     pub layers: HashMap<String, LayerDefinition>,
+    /// Keyboard LEDs lit while a layer is active, other LEDs follow the system state
+    #[serde(default)]
+    pub indicators: HashMap<LedCode, IndicatorConfig>,
+    /// Key chatter filter, drops the presses occurring too soon after the previous release of the same key
+    #[serde(default)]
+    pub debounce: DebounceConfig,
+    /// Key repeat generated by okey instead of the kernel autorepeat, settings of remapped keys apply to the target key
+    pub repeat: Option<RepeatConfig>,
 }
 
-#[derive(Debug, Default, Deserialize)]
+impl KeyboardConfig {
+    pub fn label(&self, index: usize) -> String {
+        match self.name.is_empty() {
+            true => format!("keyboard #{index}"),
+            false => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum TemplateNames {
+    Multiple(Vec<String>),
+    Single(String),
+}
+
+impl TemplateNames {
+    pub fn names(&self) -> &[String] {
+        match self {
+            TemplateNames::Multiple(names) => names,
+            TemplateNames::Single(name) => slice::from_ref(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum DeviceMatchConfig {
+    Multiple(Vec<DeviceMatcher>),
+    Single(DeviceMatcher),
+}
+
+impl DeviceMatchConfig {
+    pub fn matchers(&self) -> &[DeviceMatcher] {
+        match self {
+            DeviceMatchConfig::Multiple(matchers) => matchers,
+            DeviceMatchConfig::Single(matcher) => slice::from_ref(matcher),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
+pub struct DeviceMatcher {
+    pub name: Option<Pattern>,
+    /// Vendor ID
+    pub vendor: Option<u16>,
+    /// Product ID
+    pub product: Option<u16>,
+    pub phys: Option<Pattern>,
+    pub uniq: Option<Pattern>,
+    pub path: Option<Pattern>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn is_match(&self, value: &str) -> bool {
+        self.0.is_match(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum PatternConfig {
+            Glob(String),
+            Regex { regex: String },
+        }
+
+        let source = match PatternConfig::deserialize(deserializer)? {
+            PatternConfig::Glob(glob) => glob_to_regex(&glob),
+            PatternConfig::Regex { regex } => regex,
+        };
+
+        Regex::new(&source).map(Pattern).map_err(de::Error::custom)
+    }
+}
+
+impl JsonSchema for Pattern {
+    fn schema_name() -> Cow<'static, str> {
+        "Pattern".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Glob pattern (`*`, `?` and `[...]`) or regular expression",
+            "anyOf": [
+                { "type": "string" },
+                {
+                    "type": "object",
+                    "properties": { "regex": { "type": "string" } },
+                    "required": ["regex"]
+                }
+            ]
+        })
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                regex.push('[');
+
+                if chars.next_if_eq(&'!').is_some() {
+                    regex.push('^');
+                }
+
+                for char in chars.by_ref() {
+                    match char {
+                        ']' => break,
+                        '\\' | '[' | '&' | '~' => regex.push_str(&regex::escape(&char.to_string())),
+                        _ => regex.push(char),
+                    }
+                }
+
+                regex.push(']');
+            }
+            _ => regex.push_str(&regex::escape(&char.to_string())),
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
 pub struct ComboConfig(pub Vec<ComboDefinition>);
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct ComboDefinition {
     pub keys: Vec<KeyCode>,
     pub action: KeyAction,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct TapDanceConfig {
     pub timeout: Option<u16>,
     pub tap: KeyAction,
     pub hold: KeyAction,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct LayerDefinition {
+    /// Layer activation and behavior
     pub modifier: LayerModiferConfig,
+    /// Key mappings for the current layer
     pub keys: HashMap<KeyCode, KeyAction>,
+    /// Action executed when the layer becomes active
+    pub on_enter: Option<KeyAction>,
+    /// Action executed when the layer becomes inactive
+    pub on_exit: Option<KeyAction>,
+    /// Activates the layer while all the given conditions are met, checked every second
+    pub activate_when: Option<ActivationCondition>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
+pub struct ActivationCondition {
+    /// Local time window, `to` is excluded and can be before `from` to span midnight
+    pub time: Option<TimeWindow>,
+    /// Input device that must be connected
+    pub device: Option<DeviceMatcher>,
+    /// File that must exist
+    pub file: Option<PathBuf>,
+    /// Conditions that must not be met
+    pub not: Option<Box<ActivationCondition>>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+pub struct TimeWindow {
+    pub from: TimeOfDay,
+    pub to: TimeOfDay,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: TimeOfDay) -> bool {
+        match self.from <= self.to {
+            true => self.from <= time && time < self.to,
+            false => time >= self.from || time < self.to, // overnight
+        }
+    }
+}
+
+/// Minutes since midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay(pub u16);
+
+/// Shared by the parser and the schema so that both accept the same values
+const TIME_OF_DAY_PATTERN: &str = "^([01]?[0-9]|2[0-3]):([0-5][0-9])$";
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        let pattern = Regex::new(TIME_OF_DAY_PATTERN).map_err(de::Error::custom)?;
+
+        pattern
+            .captures(&value)
+            .and_then(|captures| {
+                Some((
+                    captures[1].parse::<u16>().ok()?,
+                    captures[2].parse::<u16>().ok()?,
+                ))
+            })
+            .map(|(hours, minutes)| TimeOfDay(hours * 60 + minutes))
+            .ok_or_else(|| de::Error::custom(format!("Invalid time (expected HH:MM): {value}")))
+    }
+}
+
+impl JsonSchema for TimeOfDay {
+    fn schema_name() -> Cow<'static, str> {
+        "TimeOfDay".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "pattern": TIME_OF_DAY_PATTERN
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum LayerModiferConfig {
     Simple(KeyCode),
     Custom {
+        /// Layer activation key
         key: KeyCode,
+        /// Layer activation behavior
         #[serde(default)]
         r#type: LayerModifierKind,
     },
@@ -137,7 +541,7 @@ impl LayerModiferConfig {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum LayerModifierKind {
     #[default]
@@ -146,36 +550,219 @@ pub enum LayerModifierKind {
     Oneshoot,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
+pub struct DebounceConfig {
+    /// Minimum delay between a release and the next press in milliseconds
+    #[serde(default)]
+    pub ms: u16,
+    /// Per key delay overrides
+    #[serde(default)]
+    pub keys: HashMap<KeyCode, u16>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct RepeatConfig {
+    /// Delay before the first repeat in milliseconds
+    #[serde(default = "defaults::repeat_delay")]
+    pub delay: u16,
+    /// Number of repeats per second, 0 disables repeat
+    #[serde(default = "defaults::repeat_rate")]
+    pub rate: u16,
+    /// Keys that are never repeated
+    #[serde(default)]
+    pub disabled_keys: Vec<KeyCode>,
+    /// Per key repeat settings
+    #[serde(default)]
+    pub keys: HashMap<KeyCode, RepeatKeyConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct RepeatKeyConfig {
+    pub delay: Option<u16>,
+    pub rate: Option<u16>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum IndicatorConfig {
+    /// The name of the layer
+    Simple(String),
+    Custom {
+        /// The name of the layer
+        layer: String,
+        /// Blinking period in milliseconds
+        blink: Option<u16>,
+    },
+}
+
+impl IndicatorConfig {
+    pub fn get_layer(&self) -> &str {
+        match self {
+            IndicatorConfig::Simple(layer) => layer,
+            IndicatorConfig::Custom { layer, blink: _ } => layer,
+        }
+    }
+
+    pub fn get_blink(&self) -> Option<u16> {
+        match self {
+            IndicatorConfig::Simple(_) => None,
+            IndicatorConfig::Custom { layer: _, blink } => *blink,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct LedCode(evdev::LedCode);
+
+impl LedCode {
+    pub fn value(self) -> evdev::LedCode {
+        self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for LedCode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let key = String::deserialize(deserializer)?;
+
+        evdev::LedCode::from_str(&key)
+            .map(LedCode)
+            .map_err(|_| de::Error::custom(format!("Invalid LED code: {key}")))
+    }
+}
+
+impl JsonSchema for LedCode {
+    fn schema_name() -> Cow<'static, str> {
+        "LedCode".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        let names = (0..=evdev::LedCode::LED_CHARGING.0)
+            .map(|code| format!("{:?}", evdev::LedCode(code)))
+            .collect::<Vec<_>>();
+
+        json_schema!({
+            "type": "string",
+            "enum": names
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum KeyAction {
     KeyCode(KeyCode),
     Macro(Macro),
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Macro {
     Single(EventMacro),
     Sequence(Vec<EventMacro>),
+    Repeat {
+        sequence: Vec<EventMacro>,
+        /// Number of times the sequence is executed, 0 repeats until cancelled
+        #[serde(default = "defaults::macro_repeat")]
+        repeat: u32,
+        /// Delay between repetitions in milliseconds
+        #[serde(default)]
+        interval: u32,
+    },
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum EventMacro {
     Tap(KeyCode),
-    Press { press: KeyCode },
-    Hold { hold: KeyCode },
-    Release { release: KeyCode },
-    Delay { delay: u32 },
-    String { string: String },
-    Env { env: String },
-    Unicode { unicode: String },
-    Shell { shell: String, trim: Option<bool> },
+    Press {
+        /// Keycode press event
+        press: KeyCode,
+    },
+    Hold {
+        /// Keycode hold event
+        hold: KeyCode,
+    },
+    Release {
+        /// Keycode release event
+        release: KeyCode,
+    },
+    Delay {
+        /// Wait a given delay in milliseconds
+        delay: u32,
+    },
+    String {
+        /// ASCII string
+        string: String,
+    },
+    Env {
+        /// Environment variable name
+        env: String,
+    },
+    Unicode {
+        /// Unicode characters
+        unicode: String,
+    },
+    Shell(ShellMacro),
+    Exec(ExecMacro),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema)]
+pub struct ShellMacro {
+    /// Bash shell command
+    pub shell: String,
+    /// Whether to trim trailing whitespaces or not
+    pub trim: Option<bool>,
+    /// Kill the command after a given delay in milliseconds
+    pub timeout: Option<u32>,
+    /// Shell interpreter running the command
+    pub interpreter: Option<String>,
+    /// Additional environment variables
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Working directory of the command
+    pub cwd: Option<PathBuf>,
+    /// Behavior when the command fails or times out
+    #[serde(default)]
+    pub on_error: ShellErrorMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema)]
+pub struct ExecMacro {
+    /// Bash shell command executed in the background
+    pub exec: String,
+    /// Whether to detach the process from okey or not
+    #[serde(default)]
+    pub detach: bool,
+    /// User running the command when okey is running as root
+    pub user: Option<String>,
+    /// Additional environment variables
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Working directory of the command
+    pub cwd: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellErrorMode {
+    #[default]
+    Ignore,
+    Type,
+    Notify,
 }
 
 const SAFE_KEYCODE_START: u16 = 999;
 const SHIFTED_KEYCODE_START: u16 = 800;
+const WHEEL_KEYCODE_START: u16 = 780;
+
+/// Last keycode defined in linux/input-event-codes.h
+const KEY_MAX: u16 = 0x2ff;
+
+/// Keycodes sharing their value with another one (KEY_DIRECTION and KEY_ZOOM)
+const KEYCODE_ALIASES: [&str; 2] = ["KEY_ROTATE_DISPLAY", "KEY_FULL_SCREEN"];
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct KeyCode(evdev::KeyCode);
@@ -217,6 +804,53 @@ pub enum ShiftedKeycodes {
     KeyPipe = to_shifted_code(evdev::KeyCode::KEY_BACKSLASH),
 }
 
+pub const SHIFTED_KEYCODES: [ShiftedKeycodes; 21] = [
+    ShiftedKeycodes::KeyExclamation,
+    ShiftedKeycodes::KeyAt,
+    ShiftedKeycodes::KeyHash,
+    ShiftedKeycodes::KeyDollarsign,
+    ShiftedKeycodes::KeyPercent,
+    ShiftedKeycodes::KeyCaret,
+    ShiftedKeycodes::KeyAmpersand,
+    ShiftedKeycodes::KeyStar,
+    ShiftedKeycodes::KeyLeftparen,
+    ShiftedKeycodes::KeyRightparen,
+    ShiftedKeycodes::KeyUnderscore,
+    ShiftedKeycodes::KeyPlus,
+    ShiftedKeycodes::KeyLeftcurly,
+    ShiftedKeycodes::KeyRightcurly,
+    ShiftedKeycodes::KeyColon,
+    ShiftedKeycodes::KeyDoublequote,
+    ShiftedKeycodes::KeyLess,
+    ShiftedKeycodes::KeyGreater,
+    ShiftedKeycodes::KeyQuestion,
+    ShiftedKeycodes::KeyTilde,
+    ShiftedKeycodes::KeyPipe,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[allow(clippy::enum_variant_names)]
+pub enum WheelKeycodes {
+    WheelUp = WHEEL_KEYCODE_START as isize,
+    WheelDown,
+    WheelLeft,
+    WheelRight,
+}
+
+pub const WHEEL_KEYCODES: [WheelKeycodes; 4] = [
+    WheelKeycodes::WheelUp,
+    WheelKeycodes::WheelDown,
+    WheelKeycodes::WheelLeft,
+    WheelKeycodes::WheelRight,
+];
+
+impl From<WheelKeycodes> for KeyCode {
+    fn from(value: WheelKeycodes) -> Self {
+        Self::new(value as u16)
+    }
+}
+
 impl KeyCode {
     pub fn new(code: u16) -> Self {
         Self(evdev::KeyCode::new(code))
@@ -237,6 +871,52 @@ impl KeyCode {
     pub fn unshift(self) -> Self {
         KeyCode::new(self.0.code() - SHIFTED_KEYCODE_START)
     }
+
+    pub fn to_wheel(self) -> Option<WheelKeycodes> {
+        WHEEL_KEYCODES
+            .into_iter()
+            .find(|wheel| *wheel as u16 == self.0.code())
+    }
+}
+
+impl fmt::Display for KeyCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = self.value();
+
+        if self.is_custom() {
+            let name = CUSTOM_KEYCODES.with_borrow(|keycodes| {
+                keycodes
+                    .iter()
+                    .find(|(_, value)| **value == code)
+                    .map(|(name, _)| name.clone())
+            });
+
+            return write!(f, "{}", name.unwrap_or_else(|| format!("CUSTOM_{code}")));
+        }
+
+        let shifted = SHIFTED_KEYCODES
+            .into_iter()
+            .find(|shifted| *shifted as u16 == code);
+
+        // variant names are converted like serde's SCREAMING_SNAKE_CASE
+        let name = match (shifted, self.to_wheel()) {
+            (Some(shifted), _) => format!("{shifted:?}"),
+            (_, Some(wheel)) => format!("{wheel:?}"),
+            _ => return write!(f, "{:?}", self.0),
+        };
+
+        let mut result = String::new();
+
+        for (index, char) in name.char_indices() {
+            if index > 0 && char.is_uppercase() {
+                result.push('_');
+            }
+
+            result.push(char.to_ascii_uppercase());
+        }
+
+        write!(f, "{result}")
+    }
 }
 
 thread_local! {
@@ -255,6 +935,12 @@ impl<'de> Deserialize<'de> for KeyCode {
             return Ok(KeyCode(evdev::KeyCode(shifted as u16)));
         }
 
+        let key_deserializer: StringDeserializer<D::Error> = key.clone().into_deserializer();
+
+        if let Ok(wheel) = WheelKeycodes::deserialize(key_deserializer) {
+            return Ok(KeyCode::from(wheel));
+        }
+
         if let Ok(value) = evdev::KeyCode::from_str(&key) {
             return Ok(KeyCode(value));
         }
@@ -266,3 +952,55 @@ impl<'de> Deserialize<'de> for KeyCode {
         })
     }
 }
+
+impl JsonSchema for KeyCode {
+    fn schema_name() -> Cow<'static, str> {
+        "KeyCode".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        // any other name is accepted as a custom keycode
+        json_schema!({
+            "description": "Evdev, shifted or wheel keycode, other names are custom keycodes",
+            "anyOf": [{ "type": "string" }, { "enum": known_keycodes() }]
+        })
+    }
+}
+
+/// Returns the names of the evdev, shifted and wheel keycodes
+pub fn known_keycodes() -> Vec<String> {
+    let evdev_keycodes = (0..=KEY_MAX)
+        .map(|code| format!("{:?}", evdev::KeyCode::new(code)))
+        .filter(|name| name.starts_with("KEY_") || name.starts_with("BTN_"))
+        .chain(KEYCODE_ALIASES.map(String::from));
+
+    let shifted_keycodes = SHIFTED_KEYCODES
+        .into_iter()
+        .map(|code| KeyCode::new(code as u16).to_string());
+
+    let wheel_keycodes = WHEEL_KEYCODES
+        .into_iter()
+        .map(|code| KeyCode::from(code).to_string());
+
+    evdev_keycodes
+        .chain(shifted_keycodes)
+        .chain(wheel_keycodes)
+        .collect()
+}
+
+/// Generates the JSON schema of the configuration file
+pub fn config_schema() -> Schema {
+    SchemaSettings::draft07()
+        .with_transform(deny_unknown_fields)
+        .into_generator()
+        .into_root_schema_for::<Config>()
+}
+
+// unknown fields are ignored when parsing but are most likely typos
+fn deny_unknown_fields(schema: &mut Schema) {
+    if schema.get("properties").is_some() && schema.get("additionalProperties").is_none() {
+        schema.insert("additionalProperties".into(), false.into());
+    }
+
+    transform_subschemas(&mut deny_unknown_fields, schema);
+}
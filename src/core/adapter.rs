@@ -1,20 +1,34 @@
-use std::{thread, time::Duration};
+use std::{
+    mem,
+    sync::mpsc::{Receiver, Sender, TryRecvError},
+    time::Instant,
+};
 
 use anyhow::Result;
 use evdev::{Device, EventType, InputEvent};
 
-use crate::config::schema::{
-    DefaultConfig, GeneralConfig, KeyAction, KeyCode, KeyboardConfig, Macro,
+use crate::{
+    config::schema::{
+        DefaultConfig, EventMacro, GeneralConfig, KeyAction, KeyCode, KeyboardConfig, Macro,
+    },
+    fs::device::DeviceInfo,
 };
 
 use super::{
     buffer::InputBuffer,
     combo::ComboManager,
-    event::{IntoInputEvent, ToInputResult, HOLD_EVENT, PRESS_EVENT, RELEASE_EVENT},
+    condition::ConditionManager,
+    debounce::DebounceFilter,
+    event::{IntoInputEvent, HOLD_EVENT, PRESS_EVENT, RELEASE_EVENT},
+    indicator::IndicatorManager,
     layer::LayerManager,
     mapping::MappingManager,
-    proxy::EventProxy,
+    proxy::{EventProxy, InputProxy},
+    repeat::RepeatManager,
+    scheduler::MacroScheduler,
+    shared::RawKeyCode,
     tap_dance::TapDanceManager,
+    wheel::{is_wheel_event, key_to_wheel, wheel_to_keys},
 };
 
 #[derive(Debug)]
@@ -27,19 +41,54 @@ pub enum InputResult {
     DoubleSequence(Box<[InputResult; 2]>),
     Raw(Vec<InputEvent>),
     Delay(u32),
+    Command(Receiver<Result<String>>),
     None,
 }
 
+#[derive(Debug)]
+pub enum AdapterCommand {
+    AddDevice(Box<Device>),
+    Reload(Box<KeyboardConfig>, DefaultConfig),
+    Devices(Vec<DeviceInfo>),
+    Control(ControlCommand, Sender<AdapterState>),
+}
+
+#[derive(Debug, Clone)]
+pub enum ControlCommand {
+    State,
+    ActivateLayer(String),
+    DeactivateLayer(String),
+    ToggleLayer(String),
+    Pause,
+    Resume,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdapterState {
+    pub layers: Vec<String>,
+    pub paused: bool,
+}
+
 #[derive(Debug)]
 pub struct KeyAdapter<'a, P: EventProxy> {
     proxy: &'a mut P,
     buffer: InputBuffer,
     config: GeneralConfig,
+    debounce_filter: DebounceFilter,
+    repeat_manager: RepeatManager,
     mapping_manager: MappingManager,
     combo_manager: ComboManager,
     tap_dance_manager: TapDanceManager,
     layer_manager: LayerManager,
+    condition_manager: ConditionManager,
+    indicator_manager: IndicatorManager,
+    scheduler: MacroScheduler,
+    wheel_keys: bool,
+    paused: bool,
+    pause_changed: bool,
     depth: u8,
+    trigger: Option<RawKeyCode>,
+    cancel_key_consumed: bool,
 }
 
 impl<'a, P: EventProxy> KeyAdapter<'a, P> {
@@ -47,7 +96,11 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
         let mapping_manager = MappingManager::new(config.keys);
         let combo_manager = ComboManager::new(config.combos, defaults.combo);
         let tap_dance_manager = TapDanceManager::new(config.tap_dances, defaults.tap_dance);
+        let condition_manager = ConditionManager::new(&config.layers);
         let layer_manager = LayerManager::new(config.layers);
+        let indicator_manager = IndicatorManager::new(config.indicators);
+        let debounce_filter = DebounceFilter::new(config.debounce);
+        let repeat_manager = RepeatManager::new(config.repeat);
 
         Self {
             proxy,
@@ -55,43 +108,168 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
             tap_dance_manager,
             combo_manager,
             layer_manager,
+            condition_manager,
+            indicator_manager,
+            debounce_filter,
+            repeat_manager,
+            scheduler: MacroScheduler::default(),
+            wheel_keys: config.wheel_keys,
+            paused: false,
+            pause_changed: false,
             config: defaults.general,
             buffer: InputBuffer::default(),
             depth: 0,
+            trigger: None,
+            cancel_key_consumed: false,
         }
     }
 
-    pub fn hook(&mut self, device: &mut Device) -> Result<()> {
-        device.grab()?;
-        device.set_nonblocking(true)?;
+    /// Rebuilds the managers from the new configuration, the proxy is kept
+    pub fn reload(&mut self, config: KeyboardConfig, defaults: DefaultConfig) {
+        self.mapping_manager = MappingManager::new(config.keys);
+        self.combo_manager = ComboManager::new(config.combos, defaults.combo);
+        self.tap_dance_manager = TapDanceManager::new(config.tap_dances, defaults.tap_dance);
+        let devices = self.condition_manager.take_devices();
+
+        self.condition_manager = ConditionManager::new(&config.layers);
+        self.condition_manager.set_devices(devices);
+        self.layer_manager = LayerManager::new(config.layers);
+        self.indicator_manager = IndicatorManager::new(config.indicators);
+        self.debounce_filter = DebounceFilter::new(config.debounce);
+        self.repeat_manager = RepeatManager::new(config.repeat);
+        self.scheduler = MacroScheduler::default();
+        self.wheel_keys = config.wheel_keys;
+        self.config = defaults.general;
+        self.buffer = InputBuffer::default();
+    }
 
-        loop {
-            self.proxy.wait(self.config.event_poll_timeout)?;
+    pub fn control(&mut self, command: ControlCommand) -> AdapterState {
+        match command {
+            ControlCommand::State => {}
+            ControlCommand::ActivateLayer(name) => self.layer_manager.activate_layer(&name),
+            ControlCommand::DeactivateLayer(name) => self.layer_manager.deactivate_layer(&name),
+            ControlCommand::ToggleLayer(name) => self.layer_manager.toggle_layer(&name),
+            ControlCommand::Pause => self.set_paused(true),
+            ControlCommand::Resume => self.set_paused(false),
+        }
 
-            if let Ok(events) = device.fetch_events() {
-                for event in events {
-                    if event.event_type() == EventType::KEY {
-                        self.process_event(event)?;
-                    }
-                }
-            }
+        self.state()
+    }
 
-            self.post_process()?;
+    pub fn state(&self) -> AdapterState {
+        AdapterState {
+            layers: self
+                .layer_manager
+                .active_layers()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            paused: self.paused,
         }
     }
 
+    /// Returns the new state if the layers or the paused state changed since the last call
+    pub fn take_state_change(&mut self) -> Option<AdapterState> {
+        let layers_changed = self.layer_manager.take_changed();
+        let pause_changed = mem::take(&mut self.pause_changed);
+
+        (layers_changed || pause_changed).then(|| self.state())
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.pause_changed |= self.paused != paused;
+        self.paused = paused;
+    }
+
     pub fn process_event(&mut self, event: InputEvent) -> Result<()> {
+        if self.debounce_filter.filter(&event, Instant::now()) {
+            return Ok(());
+        }
+
+        if self.is_macro_cancel_key(event.code()) {
+            if event.value() == PRESS_EVENT {
+                self.cancel_key_consumed = self.scheduler.cancel_all();
+                self.dispatch_macros()?; // releases the keys held by the cancelled macros
+            }
+
+            // the whole keystroke is consumed when it cancelled the macros
+            if self.cancel_key_consumed {
+                self.cancel_key_consumed = event.value() != RELEASE_EVENT;
+                return Ok(());
+            }
+        }
+
         let action = self.mapping_manager.map(&event.code());
         let action = self.layer_manager.map(action);
 
-        let result = match event.value() {
+        let now = Instant::now();
+
+        if !self
+            .repeat_manager
+            .handle_event(event.code(), event.value(), &action, now)
+        {
+            return Ok(());
+        }
+
+        self.trigger = Some(event.code());
+
+        let result = self.process_action(action, event.value());
+
+        self.trigger = None;
+
+        result
+    }
+
+    pub fn process_repeat(&mut self, now: Instant) -> Result<()> {
+        if let Some(code) = self.repeat_manager.poll(now) {
+            let action = self.mapping_manager.map(&code);
+            let action = self.layer_manager.map(action);
+
+            self.process_action(action, HOLD_EVENT)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_devices(&mut self, devices: Vec<DeviceInfo>) {
+        self.condition_manager.set_devices(devices);
+    }
+
+    pub fn process_conditions(&mut self, now: Instant) {
+        for (layer, active) in self.condition_manager.poll(now) {
+            match active {
+                true => self.layer_manager.activate_layer(&layer),
+                false => self.layer_manager.deactivate_layer(&layer),
+            }
+        }
+    }
+
+    fn process_action(&mut self, action: KeyAction, value: i32) -> Result<()> {
+        let result = match value {
             PRESS_EVENT => self.handle_press(action),
             HOLD_EVENT => self.handle_hold(action),
             RELEASE_EVENT => self.handle_release(action),
             value => unreachable!("Unknown event value: {value}"),
         };
 
-        self.dispatch_result(&result)
+        self.dispatch_result(result)
+    }
+
+    /// Whether the event goes through the remapping, the others are forwarded as is
+    pub fn is_remapped_event(&self, event: &InputEvent) -> bool {
+        match event.event_type() {
+            EventType::KEY => !self.paused,
+            EventType::RELATIVE => !self.paused && self.wheel_keys && is_wheel_event(event),
+            _ => false,
+        }
+    }
+
+    pub fn process_wheel_event(&mut self, event: InputEvent) -> Result<()> {
+        for event in wheel_to_keys(&event) {
+            self.process_event(event)?;
+        }
+
+        Ok(())
     }
 
     pub fn post_process(&mut self) -> Result<()> {
@@ -99,13 +277,24 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
         self.combo_manager.process(&mut self.buffer);
 
         while let Some(result) = self.buffer.pop_result() {
-            self.dispatch_result(&result)?;
+            self.dispatch_result(result)?;
         }
 
-        Ok(())
+        self.schedule_layer_hooks();
+        self.dispatch_macros()
+    }
+
+    pub fn indicator_states(&self, now: Instant) -> Vec<(evdev::LedCode, bool)> {
+        let layers = self.layer_manager.active_layers();
+        self.indicator_manager.states(&layers, now)
+    }
+
+    #[cfg(test)]
+    pub fn has_running_macros(&self) -> bool {
+        !self.scheduler.is_idle()
     }
 
-    fn dispatch_result(&mut self, result: &InputResult) -> Result<()> {
+    fn dispatch_result(&mut self, result: InputResult) -> Result<()> {
         if self.depth > self.config.maximum_lookup_depth {
             log::warn!("Maximum keycode lookup depth exceeded");
             return Ok(());
@@ -115,39 +304,43 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
 
         match result {
             InputResult::Pending(code) => {
-                self.buffer.set_pending_key(*code);
+                self.buffer.set_pending_key(code);
             }
 
-            InputResult::Release(code) if self.buffer.is_pending_key(code) => {
-                self.dispatch_pending_key(code, result)?;
+            InputResult::Release(code) if self.buffer.is_pending_key(&code) => {
+                self.dispatch_pending_key(&code, &result)?;
             }
 
             InputResult::Press(code)
-                if !self.buffer.is_pending_key(code) && self.buffer.has_pending_keys() =>
+                if !self.buffer.is_pending_key(&code) && self.buffer.has_pending_keys() =>
             {
-                self.buffer.defer_key(*code);
+                self.buffer.defer_key(code);
             }
 
             InputResult::Press(code) | InputResult::Hold(code) | InputResult::Release(code) => {
-                self.dispatch_event_result(result, *code)?;
+                self.dispatch_event_result(&result, code)?;
             }
 
             InputResult::DoubleSequence(results) => {
-                let [first, second] = results.as_ref();
+                let [first, second] = *results;
                 self.dispatch_result(first)?;
                 self.dispatch_result(second)?;
             }
 
             InputResult::Macro(value) => {
-                self.dispatch_event_macro(value)?;
+                // nested macros are not bound to the trigger of their parent
+                let trigger = self.trigger.take();
+                self.scheduler.start(value, trigger, self.depth);
+                self.dispatch_macros()?;
             }
 
             InputResult::Raw(results) => {
-                self.proxy.emit(results)?;
+                self.proxy.emit(&results)?;
             }
 
-            InputResult::Delay(timeout) => {
-                thread::sleep(Duration::from_millis(*timeout as u64));
+            InputResult::Delay(_) | InputResult::Command(_) => {
+                self.scheduler.schedule(result, self.depth);
+                self.dispatch_macros()?;
             }
 
             InputResult::None => {}
@@ -173,12 +366,15 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
             KeyAction::KeyCode(code) if code.is_shifted() => {
                 self.dispatch_shifted_key(code, event_kind)
             }
+            KeyAction::KeyCode(code) if code.to_wheel().is_some() => {
+                self.dispatch_wheel_key(code, event_kind)
+            }
             KeyAction::KeyCode(code) if !code.is_custom() => {
                 self.proxy.emit(&[code.to_event(event_kind)])
             }
             _ => {
                 let result = handler(self, action);
-                self.dispatch_result(&result)
+                self.dispatch_result(result)
             }
         }
     }
@@ -199,6 +395,13 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
         }
     }
 
+    fn dispatch_wheel_key(&mut self, code: KeyCode, event_kind: i32) -> Result<()> {
+        match event_kind {
+            RELEASE_EVENT => Ok(()),
+            _ => self.proxy.emit(&key_to_wheel(code)),
+        }
+    }
+
     fn dispatch_pending_key(&mut self, code: &KeyCode, result: &InputResult) -> Result<()> {
         self.buffer.clear_pending_key(code);
         self.dispatch_event_result(result, *code)?;
@@ -214,34 +417,42 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
                     InputResult::Release(key),
                 ]));
 
-                self.dispatch_result(&result)?;
+                self.dispatch_result(result)?;
             }
         }
 
         Ok(())
     }
 
-    fn dispatch_event_macro(&mut self, value: &Macro) -> Result<()> {
-        let delay = self.config.unicode_input_delay;
+    fn dispatch_macros(&mut self) -> Result<()> {
+        while let Some((result, depth)) = self.scheduler.poll(&self.config) {
+            let depth = mem::replace(&mut self.depth, depth);
+            let result = self.dispatch_result(result);
 
-        let events = match value {
-            Macro::Sequence(value) => value
-                .iter()
-                .map(|m| m.to_results(delay))
-                .collect::<Result<Vec<_>>>()?
-                .into_iter()
-                .flatten()
-                .collect(),
-            Macro::Single(event) => event.to_results(delay)?,
-        };
-
-        for event in events {
-            self.dispatch_result(&event)?;
+            self.depth = depth;
+            result?;
         }
 
         Ok(())
     }
 
+    fn schedule_layer_hooks(&mut self) {
+        for action in self.layer_manager.take_hooks() {
+            let value = match action {
+                KeyAction::KeyCode(code) => Macro::Single(EventMacro::Tap(code)),
+                KeyAction::Macro(value) => value,
+            };
+
+            self.scheduler.start(value, None, self.depth);
+        }
+    }
+
+    fn is_macro_cancel_key(&self, code: u16) -> bool {
+        self.config
+            .macro_cancel_key
+            .is_some_and(|key| key.value() == code)
+    }
+
     fn handle_press(&mut self, action: KeyAction) -> InputResult {
         match action {
             KeyAction::KeyCode(code) => {
@@ -287,3 +498,91 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
         }
     }
 }
+
+impl KeyAdapter<'_, InputProxy> {
+    /// Runs the event loop, `on_change` is called whenever the layers or the paused state change
+    pub fn hook<F>(&mut self, commands: &Receiver<AdapterCommand>, mut on_change: F) -> Result<()>
+    where
+        F: FnMut(&AdapterState),
+    {
+        loop {
+            self.proxy.wait(self.config.event_poll_timeout)?;
+
+            loop {
+                match commands.try_recv() {
+                    Ok(command) => self.handle_command(command)?,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return Ok(()),
+                }
+            }
+
+            let mut frame = Vec::new();
+
+            for event in self.proxy.fetch_events()? {
+                match event.event_type() {
+                    EventType::SYNCHRONIZATION => self.process_frame(mem::take(&mut frame))?,
+                    _ => frame.push(event),
+                }
+            }
+
+            self.process_frame(frame)?;
+            self.process_repeat(Instant::now())?;
+            self.process_conditions(Instant::now());
+
+            // all the devices have been disconnected
+            if self.proxy.is_empty() {
+                return Ok(());
+            }
+
+            self.post_process()?;
+
+            let indicators = self.indicator_states(Instant::now());
+            self.proxy.sync_leds(&indicators)?;
+
+            if let Some(state) = self.take_state_change() {
+                on_change(&state);
+            }
+        }
+    }
+
+    fn process_frame(&mut self, frame: Vec<InputEvent>) -> Result<()> {
+        let (inputs, passthrough): (Vec<_>, Vec<_>) = frame
+            .into_iter()
+            .partition(|event| self.is_remapped_event(event));
+
+        // keeps the forwarded events (e.g. MSC_SCAN) in the same frame as the remapped output
+        self.proxy.defer(passthrough);
+
+        for event in inputs {
+            match event.event_type() {
+                EventType::KEY => self.process_event(event)?,
+                _ => self.process_wheel_event(event)?,
+            }
+        }
+
+        self.proxy.flush()
+    }
+
+    fn handle_command(&mut self, command: AdapterCommand) -> Result<()> {
+        match command {
+            AdapterCommand::AddDevice(device) => self.proxy.add_device(*device),
+            AdapterCommand::Reload(config, defaults) => {
+                self.proxy.release_keys()?;
+                self.reload(*config, defaults);
+                Ok(())
+            }
+            AdapterCommand::Devices(devices) => {
+                self.set_devices(devices);
+                Ok(())
+            }
+            AdapterCommand::Control(command, reply) => {
+                if let ControlCommand::Pause = command {
+                    self.proxy.release_keys()?;
+                }
+
+                let _ = reply.send(self.control(command));
+                Ok(())
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, time::Instant};
+use std::{cmp::Reverse, collections::HashSet, time::Instant};
 
 use smallvec::SmallVec;
 
@@ -29,7 +29,7 @@ impl ComboManager {
 
         let mut definitions = combos.0;
 
-        definitions.sort_by(|a, b| b.keys.len().cmp(&a.keys.len()));
+        definitions.sort_by_key(|def| Reverse(def.keys.len()));
 
         Self {
             config,
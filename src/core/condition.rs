@@ -0,0 +1,98 @@
+use std::{
+    collections::HashMap,
+    mem::{self, MaybeUninit},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::schema::{ActivationCondition, LayerDefinition, TimeOfDay},
+    fs::device::{is_device_connected, DeviceInfo},
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+pub struct ConditionManager {
+    conditions: Vec<(String, ActivationCondition)>,
+    states: HashMap<String, bool>,
+    devices: Vec<DeviceInfo>,
+    next_check: Option<Instant>,
+}
+
+impl ConditionManager {
+    pub fn new(definitions: &HashMap<String, LayerDefinition>) -> Self {
+        let conditions = definitions
+            .iter()
+            .filter_map(|(name, definition)| {
+                definition
+                    .activate_when
+                    .clone()
+                    .map(|condition| (name.clone(), condition))
+            })
+            .collect();
+
+        Self {
+            conditions,
+            ..Default::default()
+        }
+    }
+
+    /// Updates the connected input devices, reported by the daemon on hotplug
+    pub fn set_devices(&mut self, devices: Vec<DeviceInfo>) {
+        self.devices = devices;
+        self.next_check = None;
+    }
+
+    pub fn take_devices(&mut self) -> Vec<DeviceInfo> {
+        mem::take(&mut self.devices)
+    }
+
+    /// Returns the layers whose condition changed since the last check along with their new state
+    pub fn poll(&mut self, now: Instant) -> Vec<(String, bool)> {
+        if self.conditions.is_empty() || self.next_check.is_some_and(|next| next > now) {
+            return Vec::new();
+        }
+
+        self.next_check = Some(now + CHECK_INTERVAL);
+
+        let time = local_time();
+        let mut changes = Vec::new();
+
+        for (layer, condition) in &self.conditions {
+            let active = is_active(condition, time, &self.devices);
+
+            // only transitions are reported so that the layer can still be toggled manually
+            if self.states.insert(layer.clone(), active) != Some(active) {
+                changes.push((layer.clone(), active));
+            }
+        }
+
+        changes
+    }
+}
+
+fn is_active(condition: &ActivationCondition, time: TimeOfDay, devices: &[DeviceInfo]) -> bool {
+    condition.time.is_none_or(|window| window.contains(time))
+        && condition.file.as_ref().is_none_or(|path| path.exists())
+        && condition
+            .device
+            .as_ref()
+            .is_none_or(|matcher| is_device_connected(matcher, devices))
+        && condition
+            .not
+            .as_ref()
+            .is_none_or(|not| !is_active(not, time, devices))
+}
+
+fn local_time() -> TimeOfDay {
+    let mut tm = MaybeUninit::<libc::tm>::zeroed();
+
+    // SAFETY: localtime_r only writes to the given struct, a null result leaves it zeroed
+    let tm = unsafe {
+        let time = libc::time(std::ptr::null_mut());
+        libc::localtime_r(&time, tm.as_mut_ptr());
+        tm.assume_init()
+    };
+
+    TimeOfDay((tm.tm_hour * 60 + tm.tm_min) as u16)
+}
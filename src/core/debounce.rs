@@ -0,0 +1,80 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use evdev::InputEvent;
+
+use crate::config::schema::{DebounceConfig, KeyCode};
+
+use super::{
+    event::{HOLD_EVENT, PRESS_EVENT, RELEASE_EVENT},
+    shared::RawKeyCode,
+};
+
+#[derive(Debug)]
+pub struct DebounceFilter {
+    default_delay: u16,
+    delays: HashMap<RawKeyCode, u16>,
+    releases: HashMap<RawKeyCode, Instant>,
+    filtered_keys: HashSet<RawKeyCode>,
+    filtered_count: u64,
+}
+
+impl DebounceFilter {
+    pub fn new(config: DebounceConfig) -> Self {
+        Self {
+            default_delay: config.ms,
+            delays: config
+                .keys
+                .into_iter()
+                .map(|(key, delay)| (key.value(), delay))
+                .collect(),
+            releases: HashMap::new(),
+            filtered_keys: HashSet::new(),
+            filtered_count: 0,
+        }
+    }
+
+    /// Returns whether the event is a key chatter and should be dropped
+    pub fn filter(&mut self, event: &InputEvent, now: Instant) -> bool {
+        let code = event.code();
+
+        // wheel notches are synthetic taps
+        if KeyCode::new(code).to_wheel().is_some() {
+            return false;
+        }
+
+        match event.value() {
+            PRESS_EVENT if self.is_chatter(code, now) => {
+                self.filtered_keys.insert(code);
+                self.filtered_count += 1;
+
+                log::debug!(
+                    "Filtered key chatter on {:?} ({} events filtered)",
+                    evdev::KeyCode(code),
+                    self.filtered_count
+                );
+
+                true
+            }
+            HOLD_EVENT => self.filtered_keys.contains(&code),
+            // the release matching a filtered press is also dropped
+            RELEASE_EVENT if self.filtered_keys.remove(&code) => true,
+            RELEASE_EVENT => {
+                self.releases.insert(code, now);
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn is_chatter(&self, code: RawKeyCode, now: Instant) -> bool {
+        let delay = self.delays.get(&code).unwrap_or(&self.default_delay);
+        let delay = Duration::from_millis((*delay).into());
+
+        self.releases
+            .get(&code)
+            .is_some_and(|release| now.duration_since(*release) < delay)
+    }
+}
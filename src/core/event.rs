@@ -1,11 +1,13 @@
 use anyhow::Result;
 use evdev::{EventType, InputEvent};
 
-use crate::config::schema::{EventMacro, KeyCode};
+use crate::config::schema::{EventMacro, GeneralConfig, KeyCode};
 
 use super::{
     adapter::InputResult,
-    input::{command_to_input, string_to_input, unicode_to_input},
+    input::string_to_input,
+    shell::{spawn_exec, spawn_shell},
+    unicode::unicode_to_input,
 };
 
 pub const PRESS_EVENT: i32 = 1;
@@ -17,7 +19,7 @@ pub trait IntoInputEvent {
 }
 
 pub trait ToInputResult {
-    fn to_results(&self, delay: u16) -> Result<Vec<InputResult>>;
+    fn to_results(&self, config: &GeneralConfig) -> Result<Vec<InputResult>>;
 }
 
 impl IntoInputEvent for KeyCode {
@@ -27,16 +29,20 @@ impl IntoInputEvent for KeyCode {
 }
 
 impl ToInputResult for EventMacro {
-    fn to_results(&self, delay: u16) -> Result<Vec<InputResult>> {
+    fn to_results(&self, config: &GeneralConfig) -> Result<Vec<InputResult>> {
         match self {
             EventMacro::Press { press } => Ok(vec![InputResult::Press(*press)]),
             EventMacro::Hold { hold } => Ok(vec![InputResult::Hold(*hold)]),
             EventMacro::Release { release } => Ok(vec![InputResult::Release(*release)]),
             EventMacro::Delay { delay: sleep } => Ok(vec![InputResult::Delay(*sleep)]),
-            EventMacro::String { string } => string_to_input(string),
-            EventMacro::Env { env } => string_to_input(&std::env::var(env)?),
-            EventMacro::Unicode { unicode } => unicode_to_input(unicode, delay),
-            EventMacro::Shell { shell, trim } => command_to_input(shell, *trim),
+            EventMacro::String { string } => string_to_input(string, config),
+            EventMacro::Env { env } => string_to_input(&std::env::var(env)?, config),
+            EventMacro::Unicode { unicode } => unicode_to_input(unicode, config),
+            EventMacro::Shell(shell) => Ok(vec![InputResult::Command(spawn_shell(shell))]),
+            EventMacro::Exec(exec) => {
+                spawn_exec(exec)?;
+                Ok(vec![])
+            }
             EventMacro::Tap(code) => Ok(vec![InputResult::DoubleSequence(Box::new([
                 InputResult::Press(*code),
                 InputResult::Release(*code),
@@ -0,0 +1,39 @@
+use std::{collections::HashMap, time::Instant};
+
+use evdev::LedCode as RawLedCode;
+
+use crate::config::schema::{IndicatorConfig, LedCode};
+
+#[derive(Debug)]
+pub struct IndicatorManager {
+    indicators: HashMap<LedCode, IndicatorConfig>,
+    start: Instant,
+}
+
+impl IndicatorManager {
+    pub fn new(indicators: HashMap<LedCode, IndicatorConfig>) -> Self {
+        Self {
+            indicators,
+            start: Instant::now(),
+        }
+    }
+
+    /// Returns the state of the LEDs driven by an active layer, the others follow the host
+    pub fn states(&self, layers: &[&str], now: Instant) -> Vec<(RawLedCode, bool)> {
+        self.indicators
+            .iter()
+            .filter(|(_, config)| layers.contains(&config.get_layer()))
+            .map(|(led, config)| (led.value(), self.is_lit(config, now)))
+            .collect()
+    }
+
+    fn is_lit(&self, config: &IndicatorConfig, now: Instant) -> bool {
+        match config.get_blink() {
+            Some(period) if period > 0 => {
+                let elapsed = now.duration_since(self.start).as_millis();
+                (elapsed / u128::from(period)).is_multiple_of(2)
+            }
+            _ => true,
+        }
+    }
+}
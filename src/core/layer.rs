@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, mem};
 
 use smallvec::SmallVec;
 
@@ -15,19 +15,32 @@ pub struct LayerItem {
 #[derive(Debug)]
 pub struct LayerManager {
     layer_map: HashMap<RawKeyCode, LayerDefinition>,
+    names: HashMap<RawKeyCode, String>,
     layer_stack: SmallVec<[LayerItem; 5]>,
     pending: SmallVec<[LayerItem; 5]>,
+    hooks: Vec<KeyAction>,
+    changed: bool,
 }
 
 impl LayerManager {
     pub fn new(definitions: HashMap<String, LayerDefinition>) -> Self {
+        let mut layer_map = HashMap::new();
+        let mut names = HashMap::new();
+
+        for (name, definition) in definitions {
+            let modifier = definition.modifier.get_modifer().value();
+
+            layer_map.insert(modifier, definition);
+            names.insert(modifier, name);
+        }
+
         Self {
-            layer_map: definitions
-                .into_values()
-                .map(|value| (value.modifier.get_modifer().value(), value))
-                .collect(),
+            layer_map,
+            names,
             layer_stack: SmallVec::default(),
             pending: SmallVec::default(),
+            hooks: Vec::new(),
+            changed: true,
         }
     }
 
@@ -92,6 +105,53 @@ impl LayerManager {
         }
     }
 
+    pub fn activate_layer(&mut self, name: &str) {
+        if let Some(modifier) = self.find_modifier(name) {
+            if !self.is_layer_active(modifier) {
+                self.push_layer(modifier);
+            }
+        }
+    }
+
+    pub fn deactivate_layer(&mut self, name: &str) {
+        if let Some(modifier) = self.find_modifier(name) {
+            self.pop_layer(modifier);
+        }
+    }
+
+    pub fn toggle_layer(&mut self, name: &str) {
+        match self.find_modifier(name) {
+            Some(modifier) if self.is_layer_active(modifier) => self.pop_layer(modifier),
+            Some(modifier) => self.push_layer(modifier),
+            None => {}
+        }
+    }
+
+    pub fn active_layers(&self) -> Vec<&str> {
+        self.layer_stack
+            .iter()
+            .filter_map(|layer| self.names.get(&layer.modifier))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Returns the `on_enter` and `on_exit` actions of the layers pushed or popped since the last call
+    pub fn take_hooks(&mut self) -> Vec<KeyAction> {
+        mem::take(&mut self.hooks)
+    }
+
+    /// Returns whether a layer has been pushed or popped since the last call
+    pub fn take_changed(&mut self) -> bool {
+        mem::take(&mut self.changed)
+    }
+
+    fn find_modifier(&self, name: &str) -> Option<RawKeyCode> {
+        self.names
+            .iter()
+            .find(|(_, value)| *value == name)
+            .map(|(modifier, _)| *modifier)
+    }
+
     fn is_layer_active(&self, modifier: RawKeyCode) -> bool {
         self.layer_stack
             .iter()
@@ -105,6 +165,16 @@ impl LayerManager {
             modifier,
             base_layer,
         });
+
+        self.changed = true;
+
+        if let Some(action) = self
+            .layer_map
+            .get(&modifier)
+            .and_then(|l| l.on_enter.clone())
+        {
+            self.hooks.push(action);
+        }
     }
 
     fn pop_layer(&mut self, modifier: RawKeyCode) {
@@ -117,7 +187,23 @@ impl LayerManager {
             }
         }
 
+        let len = self.layer_stack.len();
+
         self.layer_stack.retain(|layer| layer.modifier != modifier);
+
+        if self.layer_stack.len() == len {
+            return;
+        }
+
+        self.changed = true;
+
+        if let Some(action) = self
+            .layer_map
+            .get(&modifier)
+            .and_then(|l| l.on_exit.clone())
+        {
+            self.hooks.push(action);
+        }
     }
 
     fn find_pending_layer(&self, modifier: RawKeyCode) -> Option<LayerItem> {
@@ -0,0 +1,383 @@
+use evdev::KeyCode;
+
+use crate::config::schema::KeyboardLayout;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    None,
+    Shift,
+    AltGr,
+    ShiftAltGr,
+}
+
+impl Modifier {
+    pub fn has_shift(self) -> bool {
+        matches!(self, Modifier::Shift | Modifier::ShiftAltGr)
+    }
+
+    pub fn has_altgr(self) -> bool {
+        matches!(self, Modifier::AltGr | Modifier::ShiftAltGr)
+    }
+}
+
+struct LayoutTable {
+    letters: &'static [(char, KeyCode)], // shifted for the uppercase variant
+    symbols: &'static [&'static [(char, KeyCode, Modifier)]],
+}
+
+pub fn char_to_key(layout: KeyboardLayout, char: char) -> Option<(KeyCode, Modifier)> {
+    let table = match layout {
+        KeyboardLayout::Us => &US,
+        KeyboardLayout::Fr => &FR,
+        KeyboardLayout::De => &DE,
+        KeyboardLayout::Dvorak => &DVORAK,
+    };
+
+    let symbol = COMMON
+        .iter()
+        .chain(table.symbols.iter().copied().flatten())
+        .find(|(c, _, _)| *c == char);
+
+    if let Some((_, code, modifier)) = symbol {
+        return Some((*code, *modifier));
+    }
+
+    let mut lowercase = char.to_lowercase();
+
+    match (lowercase.next(), lowercase.next()) {
+        (Some(lower), None) => table
+            .letters
+            .iter()
+            .find(|(c, _)| *c == lower)
+            .map(|(_, code)| match char.is_uppercase() {
+                true => (*code, Modifier::Shift),
+                false => (*code, Modifier::None),
+            }),
+        _ => None,
+    }
+}
+
+const COMMON: [(char, KeyCode, Modifier); 3] = [
+    (' ', KeyCode::KEY_SPACE, Modifier::None),
+    ('\t', KeyCode::KEY_TAB, Modifier::None),
+    ('\n', KeyCode::KEY_ENTER, Modifier::None),
+];
+
+const QWERTY_LETTERS: [(char, KeyCode); 26] = [
+    ('a', KeyCode::KEY_A),
+    ('b', KeyCode::KEY_B),
+    ('c', KeyCode::KEY_C),
+    ('d', KeyCode::KEY_D),
+    ('e', KeyCode::KEY_E),
+    ('f', KeyCode::KEY_F),
+    ('g', KeyCode::KEY_G),
+    ('h', KeyCode::KEY_H),
+    ('i', KeyCode::KEY_I),
+    ('j', KeyCode::KEY_J),
+    ('k', KeyCode::KEY_K),
+    ('l', KeyCode::KEY_L),
+    ('m', KeyCode::KEY_M),
+    ('n', KeyCode::KEY_N),
+    ('o', KeyCode::KEY_O),
+    ('p', KeyCode::KEY_P),
+    ('q', KeyCode::KEY_Q),
+    ('r', KeyCode::KEY_R),
+    ('s', KeyCode::KEY_S),
+    ('t', KeyCode::KEY_T),
+    ('u', KeyCode::KEY_U),
+    ('v', KeyCode::KEY_V),
+    ('w', KeyCode::KEY_W),
+    ('x', KeyCode::KEY_X),
+    ('y', KeyCode::KEY_Y),
+    ('z', KeyCode::KEY_Z),
+];
+
+const US_NUMBERS: [(char, KeyCode, Modifier); 20] = [
+    ('1', KeyCode::KEY_1, Modifier::None),
+    ('2', KeyCode::KEY_2, Modifier::None),
+    ('3', KeyCode::KEY_3, Modifier::None),
+    ('4', KeyCode::KEY_4, Modifier::None),
+    ('5', KeyCode::KEY_5, Modifier::None),
+    ('6', KeyCode::KEY_6, Modifier::None),
+    ('7', KeyCode::KEY_7, Modifier::None),
+    ('8', KeyCode::KEY_8, Modifier::None),
+    ('9', KeyCode::KEY_9, Modifier::None),
+    ('0', KeyCode::KEY_0, Modifier::None),
+    ('!', KeyCode::KEY_1, Modifier::Shift),
+    ('@', KeyCode::KEY_2, Modifier::Shift),
+    ('#', KeyCode::KEY_3, Modifier::Shift),
+    ('$', KeyCode::KEY_4, Modifier::Shift),
+    ('%', KeyCode::KEY_5, Modifier::Shift),
+    ('^', KeyCode::KEY_6, Modifier::Shift),
+    ('&', KeyCode::KEY_7, Modifier::Shift),
+    ('*', KeyCode::KEY_8, Modifier::Shift),
+    ('(', KeyCode::KEY_9, Modifier::Shift),
+    (')', KeyCode::KEY_0, Modifier::Shift),
+];
+
+const US: LayoutTable = LayoutTable {
+    letters: &QWERTY_LETTERS,
+    symbols: &[
+        &US_NUMBERS,
+        &[
+            ('`', KeyCode::KEY_GRAVE, Modifier::None),
+            ('-', KeyCode::KEY_MINUS, Modifier::None),
+            ('=', KeyCode::KEY_EQUAL, Modifier::None),
+            ('[', KeyCode::KEY_LEFTBRACE, Modifier::None),
+            (']', KeyCode::KEY_RIGHTBRACE, Modifier::None),
+            ('\\', KeyCode::KEY_BACKSLASH, Modifier::None),
+            (';', KeyCode::KEY_SEMICOLON, Modifier::None),
+            ('\'', KeyCode::KEY_APOSTROPHE, Modifier::None),
+            (',', KeyCode::KEY_COMMA, Modifier::None),
+            ('.', KeyCode::KEY_DOT, Modifier::None),
+            ('/', KeyCode::KEY_SLASH, Modifier::None),
+            ('~', KeyCode::KEY_GRAVE, Modifier::Shift),
+            ('_', KeyCode::KEY_MINUS, Modifier::Shift),
+            ('+', KeyCode::KEY_EQUAL, Modifier::Shift),
+            ('{', KeyCode::KEY_LEFTBRACE, Modifier::Shift),
+            ('}', KeyCode::KEY_RIGHTBRACE, Modifier::Shift),
+            ('|', KeyCode::KEY_BACKSLASH, Modifier::Shift),
+            (':', KeyCode::KEY_SEMICOLON, Modifier::Shift),
+            ('"', KeyCode::KEY_APOSTROPHE, Modifier::Shift),
+            ('<', KeyCode::KEY_COMMA, Modifier::Shift),
+            ('>', KeyCode::KEY_DOT, Modifier::Shift),
+            ('?', KeyCode::KEY_SLASH, Modifier::Shift),
+        ],
+    ],
+};
+
+// Dead keys (^, ¨, ...) are left out and go through the unicode fallback
+const FR: LayoutTable = LayoutTable {
+    letters: &[
+        ('a', KeyCode::KEY_Q),
+        ('b', KeyCode::KEY_B),
+        ('c', KeyCode::KEY_C),
+        ('d', KeyCode::KEY_D),
+        ('e', KeyCode::KEY_E),
+        ('f', KeyCode::KEY_F),
+        ('g', KeyCode::KEY_G),
+        ('h', KeyCode::KEY_H),
+        ('i', KeyCode::KEY_I),
+        ('j', KeyCode::KEY_J),
+        ('k', KeyCode::KEY_K),
+        ('l', KeyCode::KEY_L),
+        ('m', KeyCode::KEY_SEMICOLON),
+        ('n', KeyCode::KEY_N),
+        ('o', KeyCode::KEY_O),
+        ('p', KeyCode::KEY_P),
+        ('q', KeyCode::KEY_A),
+        ('r', KeyCode::KEY_R),
+        ('s', KeyCode::KEY_S),
+        ('t', KeyCode::KEY_T),
+        ('u', KeyCode::KEY_U),
+        ('v', KeyCode::KEY_V),
+        ('w', KeyCode::KEY_Z),
+        ('x', KeyCode::KEY_X),
+        ('y', KeyCode::KEY_Y),
+        ('z', KeyCode::KEY_W),
+    ],
+    symbols: &[&[
+        ('&', KeyCode::KEY_1, Modifier::None),
+        ('é', KeyCode::KEY_2, Modifier::None),
+        ('"', KeyCode::KEY_3, Modifier::None),
+        ('\'', KeyCode::KEY_4, Modifier::None),
+        ('(', KeyCode::KEY_5, Modifier::None),
+        ('-', KeyCode::KEY_6, Modifier::None),
+        ('è', KeyCode::KEY_7, Modifier::None),
+        ('_', KeyCode::KEY_8, Modifier::None),
+        ('ç', KeyCode::KEY_9, Modifier::None),
+        ('à', KeyCode::KEY_0, Modifier::None),
+        (')', KeyCode::KEY_MINUS, Modifier::None),
+        ('=', KeyCode::KEY_EQUAL, Modifier::None),
+        ('²', KeyCode::KEY_GRAVE, Modifier::None),
+        ('$', KeyCode::KEY_RIGHTBRACE, Modifier::None),
+        ('ù', KeyCode::KEY_APOSTROPHE, Modifier::None),
+        ('*', KeyCode::KEY_BACKSLASH, Modifier::None),
+        (',', KeyCode::KEY_M, Modifier::None),
+        (';', KeyCode::KEY_COMMA, Modifier::None),
+        (':', KeyCode::KEY_DOT, Modifier::None),
+        ('!', KeyCode::KEY_SLASH, Modifier::None),
+        ('<', KeyCode::KEY_102ND, Modifier::None),
+        ('1', KeyCode::KEY_1, Modifier::Shift),
+        ('2', KeyCode::KEY_2, Modifier::Shift),
+        ('3', KeyCode::KEY_3, Modifier::Shift),
+        ('4', KeyCode::KEY_4, Modifier::Shift),
+        ('5', KeyCode::KEY_5, Modifier::Shift),
+        ('6', KeyCode::KEY_6, Modifier::Shift),
+        ('7', KeyCode::KEY_7, Modifier::Shift),
+        ('8', KeyCode::KEY_8, Modifier::Shift),
+        ('9', KeyCode::KEY_9, Modifier::Shift),
+        ('0', KeyCode::KEY_0, Modifier::Shift),
+        ('°', KeyCode::KEY_MINUS, Modifier::Shift),
+        ('+', KeyCode::KEY_EQUAL, Modifier::Shift),
+        ('£', KeyCode::KEY_RIGHTBRACE, Modifier::Shift),
+        ('%', KeyCode::KEY_APOSTROPHE, Modifier::Shift),
+        ('µ', KeyCode::KEY_BACKSLASH, Modifier::Shift),
+        ('?', KeyCode::KEY_M, Modifier::Shift),
+        ('.', KeyCode::KEY_COMMA, Modifier::Shift),
+        ('/', KeyCode::KEY_DOT, Modifier::Shift),
+        ('§', KeyCode::KEY_SLASH, Modifier::Shift),
+        ('>', KeyCode::KEY_102ND, Modifier::Shift),
+        ('~', KeyCode::KEY_2, Modifier::AltGr),
+        ('#', KeyCode::KEY_3, Modifier::AltGr),
+        ('{', KeyCode::KEY_4, Modifier::AltGr),
+        ('[', KeyCode::KEY_5, Modifier::AltGr),
+        ('|', KeyCode::KEY_6, Modifier::AltGr),
+        ('`', KeyCode::KEY_7, Modifier::AltGr),
+        ('\\', KeyCode::KEY_8, Modifier::AltGr),
+        ('^', KeyCode::KEY_9, Modifier::AltGr),
+        ('@', KeyCode::KEY_0, Modifier::AltGr),
+        (']', KeyCode::KEY_MINUS, Modifier::AltGr),
+        ('}', KeyCode::KEY_EQUAL, Modifier::AltGr),
+        ('€', KeyCode::KEY_E, Modifier::AltGr),
+        ('¤', KeyCode::KEY_RIGHTBRACE, Modifier::AltGr),
+        ('¹', KeyCode::KEY_1, Modifier::AltGr),
+        ('¡', KeyCode::KEY_1, Modifier::ShiftAltGr),
+        ('¿', KeyCode::KEY_MINUS, Modifier::ShiftAltGr),
+    ]],
+};
+
+// Dead keys (^, ´, `) are left out and go through the unicode fallback
+const DE: LayoutTable = LayoutTable {
+    letters: &[
+        ('a', KeyCode::KEY_A),
+        ('b', KeyCode::KEY_B),
+        ('c', KeyCode::KEY_C),
+        ('d', KeyCode::KEY_D),
+        ('e', KeyCode::KEY_E),
+        ('f', KeyCode::KEY_F),
+        ('g', KeyCode::KEY_G),
+        ('h', KeyCode::KEY_H),
+        ('i', KeyCode::KEY_I),
+        ('j', KeyCode::KEY_J),
+        ('k', KeyCode::KEY_K),
+        ('l', KeyCode::KEY_L),
+        ('m', KeyCode::KEY_M),
+        ('n', KeyCode::KEY_N),
+        ('o', KeyCode::KEY_O),
+        ('p', KeyCode::KEY_P),
+        ('q', KeyCode::KEY_Q),
+        ('r', KeyCode::KEY_R),
+        ('s', KeyCode::KEY_S),
+        ('t', KeyCode::KEY_T),
+        ('u', KeyCode::KEY_U),
+        ('v', KeyCode::KEY_V),
+        ('w', KeyCode::KEY_W),
+        ('x', KeyCode::KEY_X),
+        ('y', KeyCode::KEY_Z),
+        ('z', KeyCode::KEY_Y),
+        ('ü', KeyCode::KEY_LEFTBRACE),
+        ('ö', KeyCode::KEY_SEMICOLON),
+        ('ä', KeyCode::KEY_APOSTROPHE),
+    ],
+    symbols: &[&[
+        ('1', KeyCode::KEY_1, Modifier::None),
+        ('2', KeyCode::KEY_2, Modifier::None),
+        ('3', KeyCode::KEY_3, Modifier::None),
+        ('4', KeyCode::KEY_4, Modifier::None),
+        ('5', KeyCode::KEY_5, Modifier::None),
+        ('6', KeyCode::KEY_6, Modifier::None),
+        ('7', KeyCode::KEY_7, Modifier::None),
+        ('8', KeyCode::KEY_8, Modifier::None),
+        ('9', KeyCode::KEY_9, Modifier::None),
+        ('0', KeyCode::KEY_0, Modifier::None),
+        ('ß', KeyCode::KEY_MINUS, Modifier::None),
+        ('+', KeyCode::KEY_RIGHTBRACE, Modifier::None),
+        ('#', KeyCode::KEY_BACKSLASH, Modifier::None),
+        (',', KeyCode::KEY_COMMA, Modifier::None),
+        ('.', KeyCode::KEY_DOT, Modifier::None),
+        ('-', KeyCode::KEY_SLASH, Modifier::None),
+        ('<', KeyCode::KEY_102ND, Modifier::None),
+        ('!', KeyCode::KEY_1, Modifier::Shift),
+        ('"', KeyCode::KEY_2, Modifier::Shift),
+        ('§', KeyCode::KEY_3, Modifier::Shift),
+        ('$', KeyCode::KEY_4, Modifier::Shift),
+        ('%', KeyCode::KEY_5, Modifier::Shift),
+        ('&', KeyCode::KEY_6, Modifier::Shift),
+        ('/', KeyCode::KEY_7, Modifier::Shift),
+        ('(', KeyCode::KEY_8, Modifier::Shift),
+        (')', KeyCode::KEY_9, Modifier::Shift),
+        ('=', KeyCode::KEY_0, Modifier::Shift),
+        ('?', KeyCode::KEY_MINUS, Modifier::Shift),
+        ('°', KeyCode::KEY_GRAVE, Modifier::Shift),
+        ('*', KeyCode::KEY_RIGHTBRACE, Modifier::Shift),
+        ('\'', KeyCode::KEY_BACKSLASH, Modifier::Shift),
+        (';', KeyCode::KEY_COMMA, Modifier::Shift),
+        (':', KeyCode::KEY_DOT, Modifier::Shift),
+        ('_', KeyCode::KEY_SLASH, Modifier::Shift),
+        ('>', KeyCode::KEY_102ND, Modifier::Shift),
+        ('²', KeyCode::KEY_2, Modifier::AltGr),
+        ('³', KeyCode::KEY_3, Modifier::AltGr),
+        ('{', KeyCode::KEY_7, Modifier::AltGr),
+        ('[', KeyCode::KEY_8, Modifier::AltGr),
+        (']', KeyCode::KEY_9, Modifier::AltGr),
+        ('}', KeyCode::KEY_0, Modifier::AltGr),
+        ('\\', KeyCode::KEY_MINUS, Modifier::AltGr),
+        ('@', KeyCode::KEY_Q, Modifier::AltGr),
+        ('€', KeyCode::KEY_E, Modifier::AltGr),
+        ('~', KeyCode::KEY_RIGHTBRACE, Modifier::AltGr),
+        ('µ', KeyCode::KEY_M, Modifier::AltGr),
+        ('|', KeyCode::KEY_102ND, Modifier::AltGr),
+        ('¹', KeyCode::KEY_1, Modifier::AltGr),
+        ('¡', KeyCode::KEY_1, Modifier::ShiftAltGr),
+        ('¿', KeyCode::KEY_MINUS, Modifier::ShiftAltGr),
+    ]],
+};
+
+const DVORAK: LayoutTable = LayoutTable {
+    letters: &[
+        ('a', KeyCode::KEY_A),
+        ('b', KeyCode::KEY_N),
+        ('c', KeyCode::KEY_I),
+        ('d', KeyCode::KEY_H),
+        ('e', KeyCode::KEY_D),
+        ('f', KeyCode::KEY_Y),
+        ('g', KeyCode::KEY_U),
+        ('h', KeyCode::KEY_J),
+        ('i', KeyCode::KEY_G),
+        ('j', KeyCode::KEY_C),
+        ('k', KeyCode::KEY_V),
+        ('l', KeyCode::KEY_P),
+        ('m', KeyCode::KEY_M),
+        ('n', KeyCode::KEY_L),
+        ('o', KeyCode::KEY_S),
+        ('p', KeyCode::KEY_R),
+        ('q', KeyCode::KEY_X),
+        ('r', KeyCode::KEY_O),
+        ('s', KeyCode::KEY_SEMICOLON),
+        ('t', KeyCode::KEY_K),
+        ('u', KeyCode::KEY_F),
+        ('v', KeyCode::KEY_DOT),
+        ('w', KeyCode::KEY_COMMA),
+        ('x', KeyCode::KEY_B),
+        ('y', KeyCode::KEY_T),
+        ('z', KeyCode::KEY_SLASH),
+    ],
+    symbols: &[
+        &US_NUMBERS,
+        &[
+            ('`', KeyCode::KEY_GRAVE, Modifier::None),
+            ('[', KeyCode::KEY_MINUS, Modifier::None),
+            (']', KeyCode::KEY_EQUAL, Modifier::None),
+            ('\'', KeyCode::KEY_Q, Modifier::None),
+            (',', KeyCode::KEY_W, Modifier::None),
+            ('.', KeyCode::KEY_E, Modifier::None),
+            ('/', KeyCode::KEY_LEFTBRACE, Modifier::None),
+            ('=', KeyCode::KEY_RIGHTBRACE, Modifier::None),
+            ('\\', KeyCode::KEY_BACKSLASH, Modifier::None),
+            ('-', KeyCode::KEY_APOSTROPHE, Modifier::None),
+            (';', KeyCode::KEY_Z, Modifier::None),
+            ('~', KeyCode::KEY_GRAVE, Modifier::Shift),
+            ('{', KeyCode::KEY_MINUS, Modifier::Shift),
+            ('}', KeyCode::KEY_EQUAL, Modifier::Shift),
+            ('"', KeyCode::KEY_Q, Modifier::Shift),
+            ('<', KeyCode::KEY_W, Modifier::Shift),
+            ('>', KeyCode::KEY_E, Modifier::Shift),
+            ('?', KeyCode::KEY_LEFTBRACE, Modifier::Shift),
+            ('+', KeyCode::KEY_RIGHTBRACE, Modifier::Shift),
+            ('|', KeyCode::KEY_BACKSLASH, Modifier::Shift),
+            ('_', KeyCode::KEY_APOSTROPHE, Modifier::Shift),
+            (':', KeyCode::KEY_Z, Modifier::Shift),
+        ],
+    ],
+};
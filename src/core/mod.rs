@@ -1,16 +1,27 @@
 mod adapter;
 mod buffer;
 mod combo;
+mod condition;
+mod debounce;
 mod event;
+mod indicator;
 mod input;
 mod layer;
+mod layout;
 mod mapping;
 mod proxy;
+mod repeat;
+mod scheduler;
 mod shared;
+mod shell;
 mod tap_dance;
+mod uinput;
+mod unicode;
+mod wheel;
 
-pub use adapter::KeyAdapter;
+pub use adapter::{AdapterCommand, AdapterState, ControlCommand, KeyAdapter};
 pub use proxy::InputProxy;
+pub use uinput::{Capabilities, VIRTUAL_NAME_SUFFIX, VIRTUAL_PRODUCT, VIRTUAL_VENDOR};
 
 #[cfg(test)]
 pub use proxy::EventProxy;
@@ -1,6 +1,16 @@
+use std::{io, mem};
+
 use anyhow::Result;
-use evdev::{Device, InputEvent, uinput::VirtualDevice};
-use nix::sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags};
+use evdev::{AttributeSet, Device, EventType, InputEvent, KeyCode, LedCode, SynchronizationCode};
+use nix::{
+    libc,
+    sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags},
+};
+
+use super::{
+    event::RELEASE_EVENT,
+    uinput::{Capabilities, VirtualDevice, VIRTUAL_NAME_SUFFIX},
+};
 
 pub trait EventProxy {
     fn emit(&mut self, events: &[InputEvent]) -> Result<()>;
@@ -12,34 +22,220 @@ pub struct InputProxy {
     epoll: Epoll,
     event_buffer: [EpollEvent; 1],
     virtual_device: VirtualDevice,
+    virtual_name: String,
+    capabilities: Capabilities,
+    devices: Vec<Device>,
+    host_leds: AttributeSet<LedCode>,
+    leds: AttributeSet<LedCode>,
+    pressed_keys: AttributeSet<KeyCode>,
+    deferred: Vec<InputEvent>,
 }
 
 impl InputProxy {
-    pub fn try_from_device(device: &Device) -> Result<Self> {
-        let name = format!("{} (virtual)", device.name().unwrap_or("Unknown device"));
-        let virtual_device = VirtualDevice::builder()?
-            .name(&name)
-            .with_keys(device.supported_keys().unwrap_or_default())?
-            .build()?;
+    pub fn try_from_devices(devices: Vec<Device>, mut capabilities: Capabilities) -> Result<Self> {
+        let name = devices
+            .first()
+            .and_then(|device| device.name())
+            .unwrap_or("Unknown device");
+
+        let virtual_name = format!("{name}{VIRTUAL_NAME_SUFFIX}");
+        let mut leds = AttributeSet::new();
+
+        for device in &devices {
+            capabilities.extend_from(device)?;
+
+            for led in device.get_led_state().unwrap_or_default().iter() {
+                leds.insert(led);
+            }
+        }
+
+        let mut proxy = Self {
+            epoll: Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC)?,
+            event_buffer: [EpollEvent::empty(); 1],
+            virtual_device: VirtualDevice::new(&virtual_name, &capabilities)?,
+            virtual_name,
+            capabilities,
+            devices: Vec::new(),
+            host_leds: leds.clone(),
+            leds,
+            pressed_keys: AttributeSet::new(),
+            deferred: Vec::new(),
+        };
+
+        proxy.epoll.add(
+            &proxy.virtual_device,
+            EpollEvent::new(EpollFlags::EPOLLIN, 0),
+        )?;
+
+        for device in devices {
+            proxy.register_device(device)?;
+        }
+
+        Ok(proxy)
+    }
+
+    pub fn add_device(&mut self, device: Device) -> Result<()> {
+        // the capabilities of a virtual device can't be updated after its creation
+        if self.capabilities.extend_from(&device)? {
+            // the new device never saw the presses, their releases would get lost
+            self.release_keys()?;
+            self.epoll.delete(&self.virtual_device)?;
+            self.virtual_device = VirtualDevice::new(&self.virtual_name, &self.capabilities)?;
+            self.epoll.add(
+                &self.virtual_device,
+                EpollEvent::new(EpollFlags::EPOLLIN, 0),
+            )?;
+        }
+
+        self.register_device(device)
+    }
+
+    fn register_device(&mut self, mut device: Device) -> Result<()> {
+        device.grab()?;
+        device.set_nonblocking(true)?;
+
+        self.epoll
+            .add(&device, EpollEvent::new(EpollFlags::EPOLLIN, 0))?;
+
+        let events = self
+            .capabilities
+            .leds()
+            .iter()
+            .map(|led| led_event(led, self.leds.contains(led)))
+            .collect::<Vec<_>>();
+
+        write_leds(&mut device, &events);
+        self.devices.push(device);
+
+        Ok(())
+    }
+
+    pub fn fetch_events(&mut self) -> Result<Vec<InputEvent>> {
+        for event in self.virtual_device.fetch_events()? {
+            if event.event_type() == EventType::LED {
+                match event.value() {
+                    0 => self.host_leds.remove(LedCode(event.code())),
+                    _ => self.host_leds.insert(LedCode(event.code())),
+                }
+            }
+        }
+
+        let mut results = Vec::new();
+        let mut error = None;
+
+        self.devices
+            .retain_mut(|device| match device.fetch_events() {
+                Ok(events) => {
+                    results.extend(events);
+                    true
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => true,
+                Err(err) if err.raw_os_error() == Some(libc::ENODEV) => false,
+                Err(err) => {
+                    error = Some(err);
+                    true
+                }
+            });
+
+        match error {
+            Some(err) => Err(err.into()),
+            None => Ok(results),
+        }
+    }
+
+    /// Writes the LED state of the host to the physical devices, unless overridden by okey
+    pub fn sync_leds(&mut self, overrides: &[(LedCode, bool)]) -> Result<()> {
+        let mut events = Vec::new();
 
-        let epoll = Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC)?;
-        let event = EpollEvent::new(EpollFlags::EPOLLIN, 0);
-        let event_buffer = [EpollEvent::empty(); 1];
+        for led in self.capabilities.leds().iter() {
+            let state = overrides
+                .iter()
+                .find(|(code, _)| *code == led)
+                .map(|(_, state)| *state)
+                .unwrap_or_else(|| self.host_leds.contains(led));
 
-        epoll.add(device, event)?;
+            if state != self.leds.contains(led) {
+                match state {
+                    true => self.leds.insert(led),
+                    false => self.leds.remove(led),
+                }
 
-        Ok(Self {
-            epoll,
-            event_buffer,
-            virtual_device,
-        })
+                events.push(led_event(led, state));
+            }
+        }
+
+        if !events.is_empty() {
+            for device in &mut self.devices {
+                write_leds(device, &events);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Releases every key held on the virtual device
+    pub fn release_keys(&mut self) -> Result<()> {
+        let events = self
+            .pressed_keys
+            .iter()
+            .map(|key| InputEvent::new(EventType::KEY.0, key.0, RELEASE_EVENT))
+            .collect::<Vec<_>>();
+
+        if !events.is_empty() {
+            self.emit(&events)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the events along with the next frame written to the virtual device
+    pub fn defer(&mut self, events: Vec<InputEvent>) {
+        self.deferred.extend(events);
+    }
+
+    /// Writes the deferred events that weren't sent with any other frame
+    pub fn flush(&mut self) -> Result<()> {
+        match self.deferred.is_empty() {
+            true => Ok(()),
+            false => self.emit(&[]),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+}
+
+impl Drop for InputProxy {
+    fn drop(&mut self) {
+        if let Err(err) = self.release_keys() {
+            log::error!("Failed to release the held keys: {err}");
+        }
+
+        for device in &mut self.devices {
+            let _ = device.ungrab();
+        }
     }
 }
 
 impl EventProxy for InputProxy {
     fn emit(&mut self, events: &[InputEvent]) -> Result<()> {
-        self.virtual_device.emit(events)?;
-        Ok(())
+        for event in events {
+            if event.event_type() == EventType::KEY {
+                match event.value() {
+                    RELEASE_EVENT => self.pressed_keys.remove(KeyCode(event.code())),
+                    _ => self.pressed_keys.insert(KeyCode(event.code())),
+                }
+            }
+        }
+
+        match self.deferred.is_empty() {
+            true => self.virtual_device.emit(events),
+            false => {
+                let frame = [mem::take(&mut self.deferred).as_slice(), events].concat();
+                self.virtual_device.emit(&frame)
+            }
+        }
     }
 
     fn wait(&mut self, timeout: u16) -> Result<()> {
@@ -47,3 +243,19 @@ impl EventProxy for InputProxy {
         Ok(())
     }
 }
+
+fn led_event(led: LedCode, state: bool) -> InputEvent {
+    InputEvent::new(EventType::LED.0, led.0, state.into())
+}
+
+fn write_leds(device: &mut Device, events: &[InputEvent]) {
+    let syn = InputEvent::new(
+        EventType::SYNCHRONIZATION.0,
+        SynchronizationCode::SYN_REPORT.0,
+        0,
+    );
+
+    if let Err(err) = device.send_events(&[events, &[syn]].concat()) {
+        log::warn!("Failed to update the LED state: {err}");
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::config::schema::{KeyAction, RepeatConfig};
+
+use super::{
+    event::{HOLD_EVENT, PRESS_EVENT, RELEASE_EVENT},
+    shared::RawKeyCode,
+};
+
+#[derive(Debug)]
+struct HeldKey {
+    code: RawKeyCode,
+    next_repeat: Instant,
+    interval: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct RepeatManager {
+    config: Option<RepeatConfig>,
+    overrides: HashMap<RawKeyCode, (u16, u16)>,
+    held_key: Option<HeldKey>,
+}
+
+impl RepeatManager {
+    pub fn new(config: Option<RepeatConfig>) -> Self {
+        let Some(config) = config else {
+            return Self::default();
+        };
+
+        let overrides = config
+            .keys
+            .iter()
+            .map(|(key, value)| {
+                let delay = value.delay.unwrap_or(config.delay);
+                let rate = value.rate.unwrap_or(config.rate);
+                (key.value(), (delay, rate))
+            })
+            .collect();
+
+        Self {
+            config: Some(config),
+            overrides,
+            held_key: None,
+        }
+    }
+
+    /// Tracks the repeated key, returns whether the event should be processed
+    pub fn handle_event(
+        &mut self,
+        code: RawKeyCode,
+        value: i32,
+        action: &KeyAction,
+        now: Instant,
+    ) -> bool {
+        if self.config.is_none() {
+            return true;
+        }
+
+        match value {
+            // kernel autorepeat is replaced
+            HOLD_EVENT => false,
+            PRESS_EVENT => {
+                // the settings of the remapped key are used
+                let target = match action {
+                    KeyAction::KeyCode(target) => target.value(),
+                    KeyAction::Macro(_) => code,
+                };
+
+                self.held_key = self.get_settings(target).map(|(delay, rate)| HeldKey {
+                    code,
+                    next_repeat: now + Duration::from_millis(delay.into()),
+                    interval: Duration::from_secs(1) / u32::from(rate),
+                });
+
+                true
+            }
+            RELEASE_EVENT => {
+                if self.held_key.as_ref().is_some_and(|key| key.code == code) {
+                    self.held_key = None;
+                }
+
+                true
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the key to repeat, if any
+    pub fn poll(&mut self, now: Instant) -> Option<RawKeyCode> {
+        let key = self.held_key.as_mut()?;
+
+        if key.next_repeat > now {
+            return None;
+        }
+
+        key.next_repeat += key.interval;
+
+        // skip the missed repeats
+        if key.next_repeat <= now {
+            key.next_repeat = now + key.interval;
+        }
+
+        Some(key.code)
+    }
+
+    fn get_settings(&self, code: RawKeyCode) -> Option<(u16, u16)> {
+        let config = self.config.as_ref()?;
+
+        if config.disabled_keys.iter().any(|key| key.value() == code) {
+            return None;
+        }
+
+        let (delay, rate) = self
+            .overrides
+            .get(&code)
+            .copied()
+            .unwrap_or((config.delay, config.rate));
+
+        (rate > 0).then_some((delay, rate))
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::VecDeque,
+    mem,
+    sync::mpsc::TryRecvError,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::config::schema::{EventMacro, GeneralConfig, KeyCode, Macro};
+
+use super::{
+    adapter::InputResult, event::ToInputResult, input::string_to_input, shared::RawKeyCode,
+};
+
+#[derive(Debug, Default)]
+pub struct MacroScheduler {
+    running: Vec<RunningMacro>,
+}
+
+impl MacroScheduler {
+    pub fn start(&mut self, value: Macro, trigger: Option<RawKeyCode>, depth: u8) {
+        // pressing the trigger of a running macro again cancels it
+        if let Some(trigger) = trigger {
+            let (cancelled, running) = mem::take(&mut self.running)
+                .into_iter()
+                .partition::<Vec<_>, _>(|running| running.trigger == Some(trigger));
+
+            self.running = running;
+
+            if !cancelled.is_empty() {
+                self.release_keys(cancelled);
+                return;
+            }
+        }
+
+        self.running.push(RunningMacro::new(value, trigger, depth));
+    }
+
+    /// Runs a single result that needs to be awaited outside of a macro
+    pub fn schedule(&mut self, result: InputResult, depth: u8) {
+        let mut running = RunningMacro::new(Macro::Sequence(Vec::new()), None, depth);
+        running.pending.push_back(result);
+        self.running.push(running);
+    }
+
+    /// Cancels the running macros, their held keys are released on the next poll
+    pub fn cancel_all(&mut self) -> bool {
+        let cancelled = mem::take(&mut self.running);
+        let any_cancelled = !cancelled.is_empty();
+
+        self.release_keys(cancelled);
+        any_cancelled
+    }
+
+    fn release_keys(&mut self, cancelled: Vec<RunningMacro>) {
+        // the last scheduled results are polled first, the keys are released in reverse order
+        for running in cancelled {
+            for code in running.pressed {
+                self.schedule(InputResult::Release(code), running.depth);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn is_idle(&self) -> bool {
+        self.running.is_empty()
+    }
+
+    pub fn poll(&mut self, config: &GeneralConfig) -> Option<(InputResult, u8)> {
+        let now = Instant::now();
+
+        // the most recent macro goes first so that nested macros are executed inline
+        for idx in (0..self.running.len()).rev() {
+            let running = &mut self.running[idx];
+
+            match running.next_result(now, config) {
+                Ok(MacroStep::Result(result)) => return Some((result, running.depth)),
+                Ok(MacroStep::Waiting) => continue,
+                Ok(MacroStep::Done) => {
+                    self.running.remove(idx);
+                }
+                Err(err) => {
+                    log::error!("Macro execution failed: {err}");
+                    self.running.remove(idx);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+enum MacroStep {
+    Result(InputResult),
+    Waiting,
+    Done,
+}
+
+#[derive(Debug)]
+struct RunningMacro {
+    trigger: Option<RawKeyCode>,
+    events: Vec<EventMacro>,
+    index: usize,
+    pending: VecDeque<InputResult>,
+    resume_at: Option<Instant>,
+    remaining: Option<u32>,
+    interval: u32,
+    depth: u8,
+    /// Keys pressed but not released yet
+    pressed: Vec<KeyCode>,
+}
+
+impl RunningMacro {
+    fn new(source: Macro, trigger: Option<RawKeyCode>, depth: u8) -> Self {
+        let (events, repeat, interval) = match source {
+            Macro::Single(event) => (vec![event], 1, 0),
+            Macro::Sequence(events) => (events, 1, 0),
+            Macro::Repeat {
+                sequence,
+                repeat,
+                interval,
+            } => (sequence, repeat, interval),
+        };
+
+        Self {
+            trigger,
+            events,
+            index: 0,
+            pending: VecDeque::new(),
+            resume_at: None,
+            remaining: (repeat > 0).then(|| repeat - 1), // 0 repeats until cancelled
+            interval,
+            depth,
+            pressed: Vec::new(),
+        }
+    }
+
+    fn next_result(&mut self, now: Instant, config: &GeneralConfig) -> Result<MacroStep> {
+        loop {
+            if self.resume_at.is_some_and(|instant| instant > now) {
+                return Ok(MacroStep::Waiting);
+            }
+
+            self.resume_at = None;
+
+            match self.pending.pop_front() {
+                Some(InputResult::Delay(delay)) => {
+                    self.sleep(now, delay);
+                }
+                Some(InputResult::Command(receiver)) => match receiver.try_recv() {
+                    Ok(output) => {
+                        let results = string_to_input(&output?, config)?;
+
+                        for result in results.into_iter().rev() {
+                            self.pending.push_front(result);
+                        }
+                    }
+                    Err(TryRecvError::Empty) => {
+                        self.pending.push_front(InputResult::Command(receiver));
+                        return Ok(MacroStep::Waiting);
+                    }
+                    Err(TryRecvError::Disconnected) => {}
+                },
+                Some(result) => {
+                    self.track_key(&result);
+                    return Ok(MacroStep::Result(result));
+                }
+                None if self.index < self.events.len() => {
+                    let results = self.events[self.index].to_results(config)?;
+                    self.pending.extend(results);
+                    self.index += 1;
+                }
+                None if self.remaining.is_none_or(|count| count > 0) => {
+                    self.remaining = self.remaining.map(|count| count - 1);
+                    self.index = 0;
+                    self.sleep(now, self.interval.max(1)); // let the event loop run in between
+                }
+                None => {
+                    return Ok(MacroStep::Done);
+                }
+            }
+        }
+    }
+
+    fn track_key(&mut self, result: &InputResult) {
+        match result {
+            InputResult::Press(code) | InputResult::Hold(code) if !self.pressed.contains(code) => {
+                self.pressed.push(*code);
+            }
+            InputResult::Release(code) => self.pressed.retain(|key| key != code),
+            _ => {}
+        }
+    }
+
+    fn sleep(&mut self, now: Instant, delay: u32) {
+        if delay > 0 {
+            self.resume_at = Some(now + Duration::from_millis(delay.into()));
+        }
+    }
+}
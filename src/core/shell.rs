@@ -0,0 +1,285 @@
+use std::{
+    io::{self, Read},
+    os::unix::process::CommandExt,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use nix::{
+    errno::Errno,
+    libc,
+    sys::signal::{self, Signal},
+    unistd::{self, ForkResult, Pid, User},
+};
+
+use crate::config::schema::{ExecMacro, ShellErrorMode, ShellMacro};
+
+const DEFAULT_INTERPRETER: &str = "bash";
+const STDERR_TIMEOUT: Duration = Duration::from_millis(100);
+
+pub fn spawn_shell(value: &ShellMacro) -> Receiver<Result<String>> {
+    let (sender, receiver) = mpsc::channel();
+    let value = value.clone();
+    let trim = value.trim.unwrap_or_default();
+
+    thread::spawn(move || {
+        let output = execute(&value).map(|output| match trim {
+            true => output.trim().to_string(),
+            false => output,
+        });
+
+        let _ = sender.send(output);
+    });
+
+    receiver
+}
+
+pub fn spawn_exec(value: &ExecMacro) -> Result<()> {
+    let mut process = Command::new(DEFAULT_INTERPRETER);
+
+    process
+        .arg("-c")
+        .arg(&value.exec)
+        .envs(&value.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    if let Some(cwd) = &value.cwd {
+        process.current_dir(cwd);
+    }
+
+    if let Some(user) = &value.user {
+        set_user(&mut process, user)?;
+    }
+
+    if value.detach {
+        // SAFETY: only async-signal-safe functions are called between fork and exec
+        unsafe { process.pre_exec(detach) };
+    }
+
+    let mut child = process.spawn()?;
+    let command = value.exec.clone();
+
+    // reaps the child, or the intermediate process when detached
+    thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => log::error!("Command `{command}` failed ({status})"),
+        Err(err) => log::error!("Command `{command}` failed: {err}"),
+        _ => {}
+    });
+
+    Ok(())
+}
+
+struct ShellOutput {
+    status: Option<ExitStatus>,
+    stdout: String,
+    stderr: String,
+}
+
+fn execute(value: &ShellMacro) -> Result<String> {
+    let command = &value.shell;
+
+    let (error, stderr) = match run(value) {
+        Ok(output) => match output.status {
+            Some(status) if status.success() => {
+                if !output.stderr.trim().is_empty() {
+                    log::warn!("Shell command `{command}` stderr: {}", output.stderr.trim());
+                }
+
+                return Ok(output.stdout);
+            }
+            Some(status) => (
+                format!("Shell command `{command}` failed ({status})"),
+                output.stderr,
+            ),
+            None => (
+                format!("Shell command `{command}` timed out"),
+                output.stderr,
+            ),
+        },
+        Err(err) => (
+            format!("Shell command `{command}` failed: {err}"),
+            String::new(),
+        ),
+    };
+
+    let stderr = stderr.trim();
+
+    log::error!("{error}");
+
+    if !stderr.is_empty() {
+        log::error!("Shell command `{command}` stderr: {stderr}");
+    }
+
+    match value.on_error {
+        ShellErrorMode::Ignore => Ok(String::new()),
+        ShellErrorMode::Type if stderr.is_empty() => Ok(error),
+        ShellErrorMode::Type => Ok(stderr.to_string()),
+        ShellErrorMode::Notify => {
+            notify(&error, stderr);
+            Ok(String::new())
+        }
+    }
+}
+
+fn run(value: &ShellMacro) -> Result<ShellOutput> {
+    let interpreter = value.interpreter.as_deref().unwrap_or(DEFAULT_INTERPRETER);
+    let mut process = Command::new(interpreter);
+
+    process
+        .arg("-c")
+        .arg(&value.shell)
+        .envs(&value.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0); // allows killing the whole script on timeout
+
+    if let Some(cwd) = &value.cwd {
+        process.current_dir(cwd);
+    }
+
+    let mut child = process.spawn()?;
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let deadline = value
+        .timeout
+        .map(|timeout| Instant::now() + Duration::from_millis(timeout.into()));
+
+    let (status, stdout) = match wait_with_timeout(&mut child, deadline)? {
+        // background processes may keep stdout open after the shell exits
+        Some(status) => match recv_before(&stdout, deadline) {
+            Some(output) => (Some(status), output),
+            None => {
+                match kill_group(&child) {
+                    Ok(()) | Err(Errno::ESRCH) => {} // the background processes may be gone
+                    Err(err) => return Err(err.into()),
+                }
+
+                (None, String::new())
+            }
+        },
+        None => (None, String::new()),
+    };
+
+    Ok(ShellOutput {
+        status,
+        stdout,
+        stderr: stderr.recv_timeout(STDERR_TIMEOUT).unwrap_or_default(),
+    })
+}
+
+/// Returns `None` if the output is not available before the deadline
+fn recv_before(output: &Receiver<String>, deadline: Option<Instant>) -> Option<String> {
+    let Some(deadline) = deadline else {
+        return Some(output.recv().unwrap_or_default());
+    };
+
+    match output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(output) => Some(output),
+        Err(RecvTimeoutError::Disconnected) => Some(String::new()),
+        Err(RecvTimeoutError::Timeout) => None,
+    }
+}
+
+fn read_pipe<R>(pipe: Option<R>) -> Receiver<String>
+where
+    R: Read + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+
+        let _ = sender.send(String::from_utf8_lossy(&buffer).into_owned());
+    });
+
+    receiver
+}
+
+fn wait_with_timeout(child: &mut Child, deadline: Option<Instant>) -> Result<Option<ExitStatus>> {
+    let Some(deadline) = deadline else {
+        return Ok(Some(child.wait()?));
+    };
+
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    // the child may have exited right at the deadline
+    if let Some(status) = child.try_wait()? {
+        return Ok(Some(status));
+    }
+
+    match kill_group(child) {
+        Ok(()) => {
+            child.wait()?;
+            Ok(None)
+        }
+        Err(Errno::ESRCH) => Ok(Some(child.wait()?)), // already exited
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Kills the script along with the processes it started in the background
+fn kill_group(child: &Child) -> nix::Result<()> {
+    signal::killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL)
+}
+
+fn notify(summary: &str, body: &str) {
+    let result = Command::new("notify-send")
+        .args(["--app-name=okey", summary, body])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+
+    if let Err(err) = result {
+        log::error!("Failed to send notification: {err}");
+    }
+}
+
+fn set_user(process: &mut Command, name: &str) -> Result<()> {
+    if !unistd::geteuid().is_root() {
+        log::warn!("Not running as root, ignoring user {name}");
+        return Ok(());
+    }
+
+    let user = User::from_name(name)?.ok_or(anyhow!("User not found: {name}"))?;
+    let runtime_dir = format!("/run/user/{}", user.uid);
+
+    process
+        .uid(user.uid.as_raw())
+        .gid(user.gid.as_raw())
+        .env("HOME", &user.dir)
+        .env("USER", &user.name)
+        .env("LOGNAME", &user.name)
+        .env(
+            "DBUS_SESSION_BUS_ADDRESS",
+            format!("unix:path={runtime_dir}/bus"),
+        )
+        .env("XDG_RUNTIME_DIR", runtime_dir);
+
+    Ok(())
+}
+
+// Double fork so that the process is adopted by init instead of okey
+fn detach() -> io::Result<()> {
+    unistd::setsid()?;
+
+    match unsafe { unistd::fork() }? {
+        ForkResult::Parent { .. } => unsafe { libc::_exit(0) },
+        ForkResult::Child => Ok(()),
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    mem,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd},
+        unix::fs::OpenOptionsExt,
+    },
+    slice,
+};
+
+use anyhow::Result;
+use evdev::{
+    AbsInfo, AbsoluteAxisCode, AttributeSet, BusType, Device, EventType, InputEvent, KeyCode,
+    LedCode, MiscCode, PropType, RelativeAxisCode, SwitchCode, SynchronizationCode,
+};
+use nix::{ioctl_none, ioctl_write_int, ioctl_write_ptr, libc};
+
+const UINPUT_PATH: &str = "/dev/uinput";
+
+pub const VIRTUAL_VENDOR: u16 = 0x1234;
+pub const VIRTUAL_PRODUCT: u16 = 0x5678;
+pub const VIRTUAL_NAME_SUFFIX: &str = " (virtual)";
+const UINPUT_IOCTL_BASE: u8 = b'U';
+
+ioctl_none!(ui_dev_create, UINPUT_IOCTL_BASE, 1);
+ioctl_none!(ui_dev_destroy, UINPUT_IOCTL_BASE, 2);
+ioctl_write_ptr!(ui_dev_setup, UINPUT_IOCTL_BASE, 3, libc::uinput_setup);
+ioctl_write_ptr!(ui_abs_setup, UINPUT_IOCTL_BASE, 4, libc::uinput_abs_setup);
+ioctl_write_int!(ui_set_evbit, UINPUT_IOCTL_BASE, 100);
+ioctl_write_int!(ui_set_keybit, UINPUT_IOCTL_BASE, 101);
+ioctl_write_int!(ui_set_relbit, UINPUT_IOCTL_BASE, 102);
+ioctl_write_int!(ui_set_absbit, UINPUT_IOCTL_BASE, 103);
+ioctl_write_int!(ui_set_mscbit, UINPUT_IOCTL_BASE, 104);
+ioctl_write_int!(ui_set_ledbit, UINPUT_IOCTL_BASE, 105);
+ioctl_write_int!(ui_set_swbit, UINPUT_IOCTL_BASE, 109);
+ioctl_write_int!(ui_set_propbit, UINPUT_IOCTL_BASE, 110);
+
+// AttributeSet's item trait is private so this can't be a generic function
+macro_rules! merge {
+    ($target:expr, $source:expr) => {{
+        let mut changed = false;
+
+        for value in $source.into_iter().flat_map(|set| set.iter()) {
+            if !$target.contains(value) {
+                $target.insert(value);
+                changed = true;
+            }
+        }
+
+        changed
+    }};
+}
+
+/// Capabilities of an input device, evdev's virtual device builder doesn't support LEDs
+#[derive(Debug, Default, Clone)]
+pub struct Capabilities {
+    keys: AttributeSet<KeyCode>,
+    relative_axes: AttributeSet<RelativeAxisCode>,
+    absolute_axes: Vec<(AbsoluteAxisCode, AbsInfo)>,
+    switches: AttributeSet<SwitchCode>,
+    leds: AttributeSet<LedCode>,
+    misc: AttributeSet<MiscCode>,
+    properties: AttributeSet<PropType>,
+}
+
+impl Capabilities {
+    /// Adds the capabilities of the device, returns whether new capabilities were added
+    pub fn extend_from(&mut self, device: &Device) -> Result<bool> {
+        let mut changed = false;
+
+        changed |= merge!(self.keys, device.supported_keys());
+        changed |= merge!(self.relative_axes, device.supported_relative_axes());
+        changed |= merge!(self.switches, device.supported_switches());
+        changed |= merge!(self.leds, device.supported_leds());
+        changed |= merge!(self.misc, device.misc_properties());
+        changed |= merge!(self.properties, Some(device.properties()));
+
+        if device.supported_absolute_axes().is_some() {
+            for (axis, info) in device.get_absinfo()? {
+                if !self.absolute_axes.iter().any(|(value, _)| *value == axis) {
+                    self.absolute_axes.push((axis, info));
+                    changed = true;
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+
+    pub fn leds(&self) -> &AttributeSet<LedCode> {
+        &self.leds
+    }
+
+    /// Adds the wheel axes used by the wheel keycodes
+    pub fn insert_wheel(&mut self) {
+        for axis in [
+            RelativeAxisCode::REL_WHEEL,
+            RelativeAxisCode::REL_HWHEEL,
+            RelativeAxisCode::REL_WHEEL_HI_RES,
+            RelativeAxisCode::REL_HWHEEL_HI_RES,
+        ] {
+            self.relative_axes.insert(axis);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VirtualDevice {
+    file: File,
+}
+
+impl VirtualDevice {
+    pub fn new(name: &str, capabilities: &Capabilities) -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open(UINPUT_PATH)?;
+
+        let fd = file.as_raw_fd();
+
+        // SAFETY: the ioctls are called on a valid uinput file descriptor
+        unsafe {
+            set_bits(
+                fd,
+                EventType::KEY,
+                ui_set_keybit,
+                capabilities.keys.iter().map(|v| v.0),
+            )?;
+            set_bits(
+                fd,
+                EventType::RELATIVE,
+                ui_set_relbit,
+                capabilities.relative_axes.iter().map(|v| v.0),
+            )?;
+            set_bits(
+                fd,
+                EventType::SWITCH,
+                ui_set_swbit,
+                capabilities.switches.iter().map(|v| v.0),
+            )?;
+            set_bits(
+                fd,
+                EventType::LED,
+                ui_set_ledbit,
+                capabilities.leds.iter().map(|v| v.0),
+            )?;
+            set_bits(
+                fd,
+                EventType::MISC,
+                ui_set_mscbit,
+                capabilities.misc.iter().map(|v| v.0),
+            )?;
+
+            for property in capabilities.properties.iter() {
+                ui_set_propbit(fd, property.0.into())?;
+            }
+
+            for (axis, info) in &capabilities.absolute_axes {
+                let setup = libc::uinput_abs_setup {
+                    code: axis.0,
+                    absinfo: libc::input_absinfo {
+                        value: info.value(),
+                        minimum: info.minimum(),
+                        maximum: info.maximum(),
+                        fuzz: info.fuzz(),
+                        flat: info.flat(),
+                        resolution: info.resolution(),
+                    },
+                };
+
+                ui_set_evbit(fd, EventType::ABSOLUTE.0.into())?;
+                ui_set_absbit(fd, axis.0.into())?;
+                ui_abs_setup(fd, &setup)?;
+            }
+
+            let mut setup: libc::uinput_setup = mem::zeroed();
+            let name = name.as_bytes();
+            let length = name.len().min(libc::UINPUT_MAX_NAME_SIZE - 1);
+
+            for (target, byte) in setup.name.iter_mut().zip(&name[..length]) {
+                *target = *byte as libc::c_char;
+            }
+
+            setup.id.bustype = BusType::BUS_VIRTUAL.0;
+            setup.id.vendor = VIRTUAL_VENDOR;
+            setup.id.product = VIRTUAL_PRODUCT;
+
+            ui_dev_setup(fd, &setup)?;
+            ui_dev_create(fd)?;
+        }
+
+        Ok(Self { file })
+    }
+
+    /// Writes the events followed by a `SYN_REPORT`
+    pub fn emit(&mut self, events: &[InputEvent]) -> Result<()> {
+        let syn = InputEvent::new(
+            EventType::SYNCHRONIZATION.0,
+            SynchronizationCode::SYN_REPORT.0,
+            0,
+        );
+
+        self.file.write_all(to_bytes(events))?;
+        self.file.write_all(to_bytes(&[syn]))?;
+
+        Ok(())
+    }
+
+    /// Reads the events sent to the virtual device by the host (e.g. LED state)
+    pub fn fetch_events(&mut self) -> Result<Vec<InputEvent>> {
+        let mut results = Vec::new();
+        let mut buffer = [0u8; mem::size_of::<libc::input_event>()];
+
+        loop {
+            match self.file.read_exact(&mut buffer) {
+                Ok(()) => {
+                    // SAFETY: the kernel only writes complete input_event structs
+                    let event: libc::input_event = unsafe { mem::transmute(buffer) };
+                    results.push(InputEvent::from(event));
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(results),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl AsFd for VirtualDevice {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl Drop for VirtualDevice {
+    fn drop(&mut self) {
+        // SAFETY: the file descriptor is still valid
+        let _ = unsafe { ui_dev_destroy(self.file.as_raw_fd()) };
+    }
+}
+
+unsafe fn set_bits<I>(
+    fd: i32,
+    event_type: EventType,
+    set_bit: unsafe fn(i32, nix::sys::ioctl::ioctl_param_type) -> nix::Result<i32>,
+    values: I,
+) -> nix::Result<()>
+where
+    I: Iterator<Item = u16>,
+{
+    let mut values = values.peekable();
+
+    if values.peek().is_some() {
+        ui_set_evbit(fd, event_type.0.into())?;
+    }
+
+    for value in values {
+        set_bit(fd, value.into())?;
+    }
+
+    Ok(())
+}
+
+fn to_bytes(events: &[InputEvent]) -> &[u8] {
+    // SAFETY: InputEvent is a transparent wrapper around input_event
+    unsafe { slice::from_raw_parts(events.as_ptr() as *const u8, mem::size_of_val(events)) }
+}
@@ -0,0 +1,260 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::config::schema::{
+    GeneralConfig, KeyCode, PasteShortcut, UnicodeMethod, UnicodeMethodKind,
+};
+
+use super::{
+    adapter::InputResult,
+    event::{IntoInputEvent, HOLD_EVENT, PRESS_EVENT, RELEASE_EVENT},
+    input::{char_to_input, hex_to_input},
+    layout::char_to_key,
+};
+
+pub fn unicode_to_input(source: &str, config: &GeneralConfig) -> Result<Vec<InputResult>> {
+    match &config.unicode_method {
+        UnicodeMethod::Simple(UnicodeMethodKind::Gtk) => gtk_to_input(source, config),
+        UnicodeMethod::Simple(UnicodeMethodKind::Compose) => {
+            let compose_key = KeyCode::from(evdev::KeyCode::KEY_COMPOSE);
+            compose_to_input(source, compose_key, config)
+        }
+        UnicodeMethod::Compose { compose } => compose_to_input(source, *compose, config),
+        UnicodeMethod::Clipboard { clipboard, paste } => {
+            clipboard_to_input(source, clipboard, *paste, config)
+        }
+        UnicodeMethod::Command { command } => Ok(external_to_input(source, command)),
+    }
+}
+
+pub fn gtk_to_input(source: &str, config: &GeneralConfig) -> Result<Vec<InputResult>> {
+    let ctrl = KeyCode::from(evdev::KeyCode::KEY_LEFTCTRL);
+    let shift = KeyCode::from(evdev::KeyCode::KEY_LEFTSHIFT);
+    let (u, _) = char_to_key(config.layout, 'u').ok_or(anyhow!("Invalid character literal"))?;
+    let u = KeyCode::from(u);
+    let enter = KeyCode::from(evdev::KeyCode::KEY_ENTER);
+
+    let value = source
+        .chars()
+        .map(|c| format!("{:x}", c as u32))
+        .map(|c| hex_to_input(&c, config.layout))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flat_map(|value| {
+            let ctrl_shift_u = [InputResult::Raw(vec![
+                ctrl.to_event(PRESS_EVENT),
+                shift.to_event(PRESS_EVENT),
+                u.to_event(PRESS_EVENT),
+                ctrl.to_event(HOLD_EVENT),
+                shift.to_event(HOLD_EVENT),
+                u.to_event(HOLD_EVENT),
+                ctrl.to_event(RELEASE_EVENT),
+                shift.to_event(RELEASE_EVENT),
+                u.to_event(RELEASE_EVENT),
+            ])];
+
+            ctrl_shift_u
+                .into_iter()
+                .chain(value)
+                .chain([
+                    InputResult::Raw(vec![
+                        enter.to_event(PRESS_EVENT),
+                        enter.to_event(RELEASE_EVENT),
+                    ]),
+                    InputResult::Delay(config.unicode_input_delay.into()),
+                ])
+                .collect::<Vec<_>>()
+        });
+
+    Ok(value.collect())
+}
+
+pub fn compose_to_input(
+    source: &str,
+    compose_key: KeyCode,
+    config: &GeneralConfig,
+) -> Result<Vec<InputResult>> {
+    let mut results = Vec::new();
+
+    for char in source.chars() {
+        let sequence = find_compose_sequence(char).and_then(|sequence| {
+            sequence
+                .chars()
+                .map(|char| char_to_input(char, config.layout))
+                .collect::<Option<Vec<_>>>()
+        });
+
+        // the sequence can also contain dead keys that are missing from the layout
+        let Some(sequence) = sequence else {
+            log::debug!("No compose sequence for {char:?}, using GTK input");
+            results.extend(gtk_to_input(&char.to_string(), config)?);
+            continue;
+        };
+
+        results.push(InputResult::Raw(vec![
+            compose_key.to_event(PRESS_EVENT),
+            compose_key.to_event(RELEASE_EVENT),
+        ]));
+
+        results.extend(sequence);
+    }
+
+    Ok(results)
+}
+
+pub fn clipboard_to_input(
+    source: &str,
+    command: &str,
+    paste: PasteShortcut,
+    config: &GeneralConfig,
+) -> Result<Vec<InputResult>> {
+    let modifiers = match paste {
+        PasteShortcut::CtrlV => vec![evdev::KeyCode::KEY_LEFTCTRL],
+        PasteShortcut::CtrlShiftV => {
+            vec![evdev::KeyCode::KEY_LEFTCTRL, evdev::KeyCode::KEY_LEFTSHIFT]
+        }
+        PasteShortcut::ShiftInsert => vec![evdev::KeyCode::KEY_LEFTSHIFT],
+    };
+
+    let key = match paste {
+        PasteShortcut::ShiftInsert => evdev::KeyCode::KEY_INSERT,
+        _ => {
+            char_to_key(config.layout, 'v')
+                .ok_or(anyhow!("Invalid character literal"))?
+                .0
+        }
+    };
+
+    let key = KeyCode::from(key);
+
+    let modifiers = modifiers.into_iter().map(KeyCode::from).collect::<Vec<_>>();
+    let mut events = Vec::new();
+
+    for modifier in &modifiers {
+        events.push(modifier.to_event(PRESS_EVENT));
+        events.push(modifier.to_event(HOLD_EVENT));
+    }
+
+    events.push(key.to_event(PRESS_EVENT));
+    events.push(key.to_event(RELEASE_EVENT));
+
+    for modifier in modifiers.iter().rev() {
+        events.push(modifier.to_event(RELEASE_EVENT));
+    }
+
+    // the paste shortcut is only sent once the clipboard has been set
+    Ok(vec![
+        InputResult::Command(spawn_with_input(command, source)),
+        InputResult::Raw(events),
+        InputResult::Delay(config.unicode_input_delay.into()),
+    ])
+}
+
+pub fn external_to_input(source: &str, command: &str) -> Vec<InputResult> {
+    vec![InputResult::Command(spawn_with_input(command, source))]
+}
+
+// Resolves to an empty output so that nothing gets typed by the scheduler
+fn spawn_with_input(command: &str, input: &str) -> Receiver<Result<String>> {
+    let (sender, receiver) = mpsc::channel();
+    let command = command.to_string();
+    let input = input.to_string();
+
+    thread::spawn(move || {
+        let _ = sender.send(run_with_input(&command, &input).map(|_| String::new()));
+    });
+
+    receiver
+}
+
+fn run_with_input(command: &str, input: &str) -> Result<()> {
+    let mut child = Command::new("bash")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+
+    if !status.success() {
+        return Err(anyhow!(
+            "Unicode input command failed ({status}): {command}"
+        ));
+    }
+
+    Ok(())
+}
+
+// Default sequences from the X11 en_US.UTF-8 compose table
+fn find_compose_sequence(char: char) -> Option<String> {
+    const ACCENTS: [(char, &str, &str); 5] = [
+        ('\'', "aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+        ('`', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+        ('^', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+        ('"', "aeiouyAEIOU", "äëïöüÿÄËÏÖÜ"),
+        ('~', "anoANO", "ãñõÃÑÕ"),
+    ];
+
+    const SYMBOLS: [(char, &str); 34] = [
+        ('ç', ",c"),
+        ('Ç', ",C"),
+        ('å', "oa"),
+        ('Å', "oA"),
+        ('æ', "ae"),
+        ('Æ', "AE"),
+        ('œ', "oe"),
+        ('Œ', "OE"),
+        ('ø', "/o"),
+        ('Ø', "/O"),
+        ('ß', "ss"),
+        ('€', "=e"),
+        ('£', "-L"),
+        ('¥', "=Y"),
+        ('¢', "|c"),
+        ('©', "oc"),
+        ('®', "or"),
+        ('™', "tm"),
+        ('°', "oo"),
+        ('±', "+-"),
+        ('×', "xx"),
+        ('÷', "-:"),
+        ('¿', "??"),
+        ('¡', "!!"),
+        ('«', "<<"),
+        ('»', ">>"),
+        ('…', ".."),
+        ('—', "---"),
+        ('–', "--."),
+        ('½', "12"),
+        ('¼', "14"),
+        ('¾', "34"),
+        ('§', "so"),
+        ('µ', "mu"),
+    ];
+
+    let accent = ACCENTS.iter().find_map(|(dead, bases, composed)| {
+        composed
+            .chars()
+            .position(|c| c == char)
+            .and_then(|idx| bases.chars().nth(idx))
+            .map(|base| format!("{dead}{base}"))
+    });
+
+    accent.or_else(|| {
+        SYMBOLS
+            .iter()
+            .find(|(c, _)| *c == char)
+            .map(|(_, sequence)| sequence.to_string())
+    })
+}
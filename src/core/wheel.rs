@@ -0,0 +1,72 @@
+use evdev::{EventType, InputEvent, RelativeAxisCode};
+
+use crate::config::schema::{KeyCode, WheelKeycodes};
+
+use super::event::{IntoInputEvent, PRESS_EVENT, RELEASE_EVENT};
+
+// the value of a single notch on high resolution wheels
+const HI_RES_NOTCH: i32 = 120;
+
+pub fn is_wheel_event(event: &InputEvent) -> bool {
+    event.event_type() == EventType::RELATIVE
+        && matches!(
+            RelativeAxisCode(event.code()),
+            RelativeAxisCode::REL_WHEEL
+                | RelativeAxisCode::REL_HWHEEL
+                | RelativeAxisCode::REL_WHEEL_HI_RES
+                | RelativeAxisCode::REL_HWHEEL_HI_RES
+        )
+}
+
+/// Converts each wheel notch into a tap of the matching wheel keycode, high resolution events are dropped
+pub fn wheel_to_keys(event: &InputEvent) -> Vec<InputEvent> {
+    let (positive, negative) = match RelativeAxisCode(event.code()) {
+        RelativeAxisCode::REL_WHEEL => (WheelKeycodes::WheelUp, WheelKeycodes::WheelDown),
+        RelativeAxisCode::REL_HWHEEL => (WheelKeycodes::WheelRight, WheelKeycodes::WheelLeft),
+        _ => return vec![],
+    };
+
+    let key = match event.value() > 0 {
+        true => KeyCode::from(positive),
+        false => KeyCode::from(negative),
+    };
+
+    (0..event.value().abs())
+        .flat_map(|_| [key.to_event(PRESS_EVENT), key.to_event(RELEASE_EVENT)])
+        .collect()
+}
+
+/// Converts a wheel keycode into a single notch, with its high resolution counterpart
+pub fn key_to_wheel(code: KeyCode) -> Vec<InputEvent> {
+    let Some(wheel) = code.to_wheel() else {
+        return vec![];
+    };
+
+    let (axis, hi_res_axis, value) = match wheel {
+        WheelKeycodes::WheelUp => (
+            RelativeAxisCode::REL_WHEEL,
+            RelativeAxisCode::REL_WHEEL_HI_RES,
+            1,
+        ),
+        WheelKeycodes::WheelDown => (
+            RelativeAxisCode::REL_WHEEL,
+            RelativeAxisCode::REL_WHEEL_HI_RES,
+            -1,
+        ),
+        WheelKeycodes::WheelLeft => (
+            RelativeAxisCode::REL_HWHEEL,
+            RelativeAxisCode::REL_HWHEEL_HI_RES,
+            -1,
+        ),
+        WheelKeycodes::WheelRight => (
+            RelativeAxisCode::REL_HWHEEL,
+            RelativeAxisCode::REL_HWHEEL_HI_RES,
+            1,
+        ),
+    };
+
+    vec![
+        InputEvent::new(EventType::RELATIVE.0, axis.0, value),
+        InputEvent::new(EventType::RELATIVE.0, hi_res_axis.0, value * HI_RES_NOTCH),
+    ]
+}
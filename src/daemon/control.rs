@@ -0,0 +1,168 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::fs::socket::find_socket_path;
+
+use super::DaemonEvent;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    Keyboards,
+    Layers {
+        keyboard: Option<String>,
+    },
+    ActivateLayer {
+        layer: String,
+        keyboard: Option<String>,
+    },
+    DeactivateLayer {
+        layer: String,
+        keyboard: Option<String>,
+    },
+    ToggleLayer {
+        layer: String,
+        keyboard: Option<String>,
+    },
+    Pause {
+        keyboard: Option<String>,
+    },
+    Resume {
+        keyboard: Option<String>,
+    },
+    Reload,
+    /// Turns the connection into a stream of keyboard states, one line per change
+    Subscribe,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keyboards: Vec<KeyboardState>,
+}
+
+impl From<Result<Vec<KeyboardState>>> for Response {
+    fn from(value: Result<Vec<KeyboardState>>) -> Self {
+        match value {
+            Ok(keyboards) => Self {
+                ok: true,
+                error: None,
+                keyboards,
+            },
+            Err(err) => Self {
+                ok: false,
+                error: Some(err.to_string()),
+                keyboards: Vec::new(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyboardState {
+    pub name: String,
+    pub devices: Vec<PathBuf>,
+    pub layers: Vec<String>,
+    pub paused: bool,
+}
+
+/// Serves JSON-lines requests, one response line per request
+pub fn serve(listener: UnixListener, sender: Sender<DaemonEvent>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let sender = sender.clone();
+
+                thread::spawn(move || {
+                    if let Err(err) = handle_client(stream, sender) {
+                        log::debug!("Control client disconnected: {err}");
+                    }
+                });
+            }
+            Err(err) => log::error!("Failed to accept a control connection: {err}"),
+        }
+    }
+}
+
+fn handle_client(stream: UnixStream, sender: Sender<DaemonEvent>) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    let reader = BufReader::new(stream);
+
+    for line in reader.lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Subscribe) => return stream_states(writer, sender),
+            Ok(request) => {
+                let (reply, receiver) = mpsc::channel();
+                sender.send(DaemonEvent::Control(request, reply))?;
+                receiver.recv()?
+            }
+            Err(err) => Response::from(Err(anyhow!("Invalid request: {err}"))),
+        };
+
+        writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+    }
+
+    Ok(())
+}
+
+fn stream_states(mut writer: UnixStream, sender: Sender<DaemonEvent>) -> Result<()> {
+    let (subscriber, receiver) = mpsc::channel();
+
+    sender.send(DaemonEvent::Subscribe(subscriber))?;
+
+    for state in receiver {
+        writeln!(writer, "{}", serde_json::to_string(&state)?)?;
+    }
+
+    Ok(())
+}
+
+fn connect() -> Result<UnixStream> {
+    let path = find_socket_path().ok_or(anyhow!("okey is not running"))?;
+    Ok(UnixStream::connect(path)?)
+}
+
+pub fn send_request(request: &Request) -> Result<Response> {
+    let mut stream = connect()?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+
+    writeln!(stream, "{}", serde_json::to_string(request)?)?;
+    reader.read_line(&mut line)?;
+
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Calls `callback` with each keyboard state sent by the daemon until the connection is closed
+pub fn subscribe<F>(mut callback: F) -> Result<()>
+where
+    F: FnMut(KeyboardState) -> Result<()>,
+{
+    let mut stream = connect()?;
+    let reader = BufReader::new(stream.try_clone()?);
+
+    writeln!(stream, "{}", serde_json::to_string(&Request::Subscribe)?)?;
+
+    for line in reader.lines() {
+        callback(serde_json::from_str(&line?)?)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,343 @@
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use x11rb::{
+    connection::Connection,
+    errors::ReplyError,
+    protocol::{
+        xproto::{AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask, Window},
+        Event,
+    },
+    rust_connection::RustConnection,
+};
+
+use crate::{
+    config::schema::{AppProfile, FocusProvider, FocusProviderKind},
+    fs::config::watch_config,
+};
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct WindowInfo {
+    #[serde(default)]
+    pub class: String,
+    #[serde(default)]
+    pub title: String,
+}
+
+/// Returns the layers of all the profiles matching the window
+pub fn profile_layers(profiles: &[AppProfile], window: &WindowInfo) -> Vec<String> {
+    let mut layers = Vec::new();
+
+    for profile in profiles {
+        if profile.matches(&window.class, &window.title) {
+            for layer in &profile.layers {
+                if !layers.contains(layer) {
+                    layers.push(layer.clone());
+                }
+            }
+        }
+    }
+
+    layers
+}
+
+/// Parses a `{ "class": ..., "title": ... }` object, anything else is used as the window class
+fn parse_window(source: &str) -> WindowInfo {
+    let source = source.trim();
+
+    serde_json::from_str(source).unwrap_or_else(|_| WindowInfo {
+        class: source.to_string(),
+        title: String::new(),
+    })
+}
+
+/// Calls `callback` with the focused window on startup and on each focus change
+pub fn watch_focus<F>(provider: &FocusProvider, mut callback: F) -> Result<()>
+where
+    F: FnMut(WindowInfo) -> Result<()>,
+{
+    match provider {
+        FocusProvider::Builtin(kind) => match resolve_provider(*kind)? {
+            FocusProviderKind::Sway => watch_sway(callback),
+            FocusProviderKind::Hyprland => watch_hyprland(callback),
+            _ => watch_x11(callback),
+        },
+        FocusProvider::Socket { socket } => {
+            let reader = BufReader::new(UnixStream::connect(socket)?);
+
+            for line in reader.lines() {
+                callback(parse_window(&line?))?;
+            }
+
+            Err(anyhow!("Focus socket closed: {}", socket.display()))
+        }
+        FocusProvider::File { file } => {
+            let mut read_window = || match fs::read_to_string(file) {
+                Ok(content) => callback(parse_window(&content)),
+                Err(err) => {
+                    log::warn!("Failed to read the focus file: {err}");
+                    Ok(())
+                }
+            };
+
+            read_window()?;
+            watch_config(file, read_window)
+        }
+    }
+}
+
+fn resolve_provider(kind: FocusProviderKind) -> Result<FocusProviderKind> {
+    match kind {
+        FocusProviderKind::Auto if env::var_os("SWAYSOCK").is_some() => Ok(FocusProviderKind::Sway),
+        FocusProviderKind::Auto if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() => {
+            Ok(FocusProviderKind::Hyprland)
+        }
+        FocusProviderKind::Auto if env::var_os("DISPLAY").is_some() => Ok(FocusProviderKind::X11),
+        FocusProviderKind::Auto => Err(anyhow!("No supported window manager found")),
+        kind => Ok(kind),
+    }
+}
+
+// https://man.archlinux.org/man/sway-ipc.7
+const SWAY_MAGIC: &[u8] = b"i3-ipc";
+const SWAY_GET_TREE: u32 = 4;
+const SWAY_SUBSCRIBE: u32 = 2;
+const SWAY_EVENT_MASK: u32 = 1 << 31;
+
+#[derive(Debug, Deserialize)]
+struct SwayWindowEvent {
+    change: String,
+    container: Value,
+}
+
+fn watch_sway<F>(mut callback: F) -> Result<()>
+where
+    F: FnMut(WindowInfo) -> Result<()>,
+{
+    let path = env::var("SWAYSOCK").map_err(|_| anyhow!("SWAYSOCK is not set"))?;
+    let mut stream = UnixStream::connect(path)?;
+
+    sway_send(&mut stream, SWAY_GET_TREE, b"")?;
+
+    let (_, tree) = sway_read(&mut stream)?;
+
+    if let Some(node) = find_focused_node(&serde_json::from_slice(&tree)?) {
+        callback(sway_window(node))?;
+    }
+
+    sway_send(&mut stream, SWAY_SUBSCRIBE, br#"["window"]"#)?;
+
+    loop {
+        let (kind, payload) = sway_read(&mut stream)?;
+
+        if kind & SWAY_EVENT_MASK == 0 {
+            continue; // subscription reply
+        }
+
+        let event = serde_json::from_slice::<SwayWindowEvent>(&payload)?;
+        let focused = event.container["focused"].as_bool().unwrap_or_default();
+
+        if event.change == "focus" || (event.change == "title" && focused) {
+            callback(sway_window(&event.container))?;
+        }
+    }
+}
+
+fn sway_send(stream: &mut UnixStream, kind: u32, payload: &[u8]) -> Result<()> {
+    let mut message = SWAY_MAGIC.to_vec();
+
+    message.extend((payload.len() as u32).to_ne_bytes());
+    message.extend(kind.to_ne_bytes());
+    message.extend(payload);
+
+    Ok(stream.write_all(&message)?)
+}
+
+fn sway_read(stream: &mut UnixStream) -> Result<(u32, Vec<u8>)> {
+    let mut header = [0; 14];
+
+    stream.read_exact(&mut header)?;
+
+    let length = u32::from_ne_bytes(header[6..10].try_into()?);
+    let kind = u32::from_ne_bytes(header[10..14].try_into()?);
+    let mut payload = vec![0; length as usize];
+
+    stream.read_exact(&mut payload)?;
+
+    Ok((kind, payload))
+}
+
+fn find_focused_node(node: &Value) -> Option<&Value> {
+    if node["focused"].as_bool().unwrap_or_default() {
+        return Some(node);
+    }
+
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(find_focused_node)
+}
+
+fn sway_window(node: &Value) -> WindowInfo {
+    // native wayland windows have an app_id, xwayland windows a class
+    let class = node["app_id"]
+        .as_str()
+        .or_else(|| node["window_properties"]["class"].as_str())
+        .unwrap_or_default();
+
+    WindowInfo {
+        class: class.to_string(),
+        title: node["name"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+// https://wiki.hypr.land/IPC
+fn watch_hyprland<F>(mut callback: F) -> Result<()>
+where
+    F: FnMut(WindowInfo) -> Result<()>,
+{
+    let directory = hyprland_directory()?;
+    let mut request = UnixStream::connect(directory.join(".socket.sock"))?;
+    let mut response = String::new();
+
+    request.write_all(b"j/activewindow")?;
+    request.read_to_string(&mut response)?;
+    callback(serde_json::from_str(&response).unwrap_or_default())?;
+
+    let reader = BufReader::new(UnixStream::connect(directory.join(".socket2.sock"))?);
+
+    for line in reader.lines() {
+        if let Some(("activewindow", data)) = line?.split_once(">>") {
+            let (class, title) = data.split_once(',').unwrap_or((data, ""));
+
+            callback(WindowInfo {
+                class: class.to_string(),
+                title: title.to_string(),
+            })?;
+        }
+    }
+
+    Err(anyhow!("Hyprland socket closed"))
+}
+
+fn hyprland_directory() -> Result<PathBuf> {
+    let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .map_err(|_| anyhow!("HYPRLAND_INSTANCE_SIGNATURE is not set"))?;
+
+    // older versions use /tmp
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").unwrap_or_default();
+
+    [
+        Path::new(&runtime_dir).join("hypr"),
+        PathBuf::from("/tmp/hypr"),
+    ]
+    .into_iter()
+    .map(|path| path.join(&signature))
+    .find(|path| path.exists())
+    .ok_or(anyhow!("Hyprland socket not found"))
+}
+
+fn watch_x11<F>(mut callback: F) -> Result<()>
+where
+    F: FnMut(WindowInfo) -> Result<()>,
+{
+    let (connection, screen) = x11rb::connect(None)?;
+    let root = connection.setup().roots[screen].root;
+    let active_window = intern_atom(&connection, b"_NET_ACTIVE_WINDOW")?;
+    let window_name = intern_atom(&connection, b"_NET_WM_NAME")?;
+    let attributes = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE);
+
+    connection.change_window_attributes(root, &attributes)?;
+
+    let mut window = x11_active_window(&connection, root, active_window)?;
+
+    loop {
+        if let Some(window) = window {
+            // also watch the title of the focused window
+            connection.change_window_attributes(window, &attributes)?;
+
+            match x11_window(&connection, window, window_name) {
+                Ok(info) => callback(info)?,
+                // BadWindow, the window has been destroyed since it was focused
+                Err(ReplyError::X11Error(err)) => {
+                    log::debug!("Focused window unavailable: {:?}", err.error_kind);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        connection.flush()?;
+
+        // errors of unchecked requests (e.g. on a destroyed window) arrive as events and are ignored
+        loop {
+            match connection.wait_for_event()? {
+                Event::PropertyNotify(event) if event.atom == active_window => {
+                    window = x11_active_window(&connection, root, active_window)?;
+                    break;
+                }
+                Event::PropertyNotify(event)
+                    if Some(event.window) == window && event.atom == window_name =>
+                {
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn intern_atom(connection: &RustConnection, name: &[u8]) -> Result<u32> {
+    Ok(connection.intern_atom(false, name)?.reply()?.atom)
+}
+
+fn x11_active_window(
+    connection: &RustConnection,
+    root: Window,
+    active_window: u32,
+) -> Result<Option<Window>> {
+    let reply = connection
+        .get_property(false, root, active_window, AtomEnum::WINDOW, 0, 1)?
+        .reply()?;
+
+    Ok(reply
+        .value32()
+        .and_then(|mut value| value.next())
+        .filter(|window| *window != 0))
+}
+
+fn x11_window(
+    connection: &RustConnection,
+    window: Window,
+    window_name: u32,
+) -> Result<WindowInfo, ReplyError> {
+    let property = |atom: u32| -> Result<Vec<u8>, ReplyError> {
+        let reply = connection
+            .get_property(false, window, atom, AtomEnum::ANY, 0, u32::MAX)?
+            .reply()?;
+
+        Ok(reply.value)
+    };
+
+    // WM_CLASS contains the instance and the class separated by a null byte
+    let class = property(AtomEnum::WM_CLASS.into())?;
+    let class = class.split(|byte| *byte == 0).nth(1).unwrap_or_default();
+
+    let mut title = property(window_name)?;
+
+    if title.is_empty() {
+        title = property(AtomEnum::WM_NAME.into())?;
+    }
+
+    Ok(WindowInfo {
+        class: String::from_utf8_lossy(class).to_string(),
+        title: String::from_utf8_lossy(&title).to_string(),
+    })
+}
@@ -0,0 +1,684 @@
+mod control;
+mod focus;
+
+pub use control::{send_request, subscribe, KeyboardState, Request, Response};
+pub use focus::{profile_layers, watch_focus, WindowInfo};
+
+use std::{
+    fs, mem,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use evdev::Device;
+use nix::sys::signal::{SigSet, Signal};
+
+use crate::{
+    config::schema::{Config, DefaultConfig, KeyboardConfig},
+    core::{AdapterCommand, AdapterState, Capabilities, ControlCommand, InputProxy, KeyAdapter},
+    fs::{
+        config::{read_config_with_files, watch_files},
+        device::{
+            find_input_devices, matches_device, open_input_device, watch_input_devices,
+            DeviceEvent, DeviceInfo,
+        },
+        socket::bind_socket,
+    },
+};
+
+#[derive(Debug)]
+pub enum DaemonEvent {
+    Device(DeviceEvent),
+    AdapterStopped(usize),
+    ConfigChanged,
+    Control(Request, Sender<Response>),
+    Subscribe(Sender<KeyboardState>),
+    StateChanged(usize, AdapterState),
+    FocusChanged(WindowInfo),
+    Signal(Signal),
+    Panic,
+}
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(1);
+const FOCUS_RETRY_DELAY: Duration = Duration::from_secs(5);
+const ADAPTER_THREAD_PREFIX: &str = "adapter-";
+
+#[derive(Debug)]
+struct AdapterHandle {
+    id: usize,
+    keyboard: usize,
+    paths: Vec<PathBuf>,
+    sender: Sender<AdapterCommand>,
+}
+
+#[derive(Debug)]
+pub struct Daemon {
+    config: Config,
+    config_path: PathBuf,
+    config_files: Vec<PathBuf>,
+    config_watcher: Option<Sender<Vec<PathBuf>>>,
+    socket_path: Option<PathBuf>,
+    adapters: Vec<AdapterHandle>,
+    devices: Vec<DeviceInfo>,
+    subscribers: Vec<Sender<KeyboardState>>,
+    profile_layers: Vec<String>,
+    focus_generation: Arc<AtomicUsize>,
+    next_id: usize,
+    sender: Sender<DaemonEvent>,
+    receiver: Receiver<DaemonEvent>,
+}
+
+impl Daemon {
+    pub fn new(config: Config, config_path: PathBuf, config_files: Vec<PathBuf>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            config,
+            config_path,
+            config_files,
+            config_watcher: None,
+            socket_path: None,
+            adapters: Vec::new(),
+            devices: Vec::new(),
+            subscribers: Vec::new(),
+            profile_layers: Vec::new(),
+            focus_generation: Arc::default(),
+            next_id: 0,
+            sender,
+            receiver,
+        }
+    }
+
+    pub fn run(mut self) -> Result<()> {
+        // must be called before spawning any thread to block the signals everywhere
+        self.spawn_signal_handler()?;
+        self.install_panic_hook();
+        self.spawn_watcher();
+        self.spawn_config_watcher();
+        self.spawn_control_server();
+        self.spawn_focus_watcher();
+        self.refresh_devices()?;
+
+        for (index, keyboard) in self.config.keyboards.iter().enumerate() {
+            if !self
+                .adapters
+                .iter()
+                .any(|adapter| adapter.keyboard == index)
+            {
+                log::warn!(
+                    "Device not found: {}, waiting for it",
+                    keyboard.label(index)
+                );
+            }
+        }
+
+        while let Ok(event) = self.receiver.recv() {
+            match event {
+                DaemonEvent::Device(DeviceEvent::Added(path)) => {
+                    if let Some(device) = open_input_device(&path) {
+                        let info = DeviceInfo::new(&path, &device);
+
+                        // udev events can be received several times for the same device
+                        if !self.devices.contains(&info) {
+                            self.devices.retain(|value| value.path != path);
+                            self.devices.push(info);
+                            self.publish_devices();
+                        }
+
+                        self.attach(vec![(path, device)]);
+                    }
+                }
+                DaemonEvent::Device(DeviceEvent::Removed(path)) => {
+                    self.devices.retain(|value| value.path != path);
+                    self.publish_devices();
+
+                    for adapter in &mut self.adapters {
+                        if adapter.paths.contains(&path) {
+                            log::info!("Device disconnected: {}", path.display());
+                            adapter.paths.retain(|value| *value != path);
+                        }
+                    }
+                }
+                DaemonEvent::AdapterStopped(id) => {
+                    self.adapters.retain(|adapter| adapter.id != id);
+                }
+                DaemonEvent::ConfigChanged | DaemonEvent::Signal(Signal::SIGHUP) => {
+                    if let Err(err) = self.reload() {
+                        log::error!(
+                            "Failed to reload the configuration, keeping the previous one: {err}"
+                        );
+                    }
+                }
+                DaemonEvent::Control(request, reply) => {
+                    let _ = reply.send(self.handle_request(request).into());
+                }
+                DaemonEvent::Subscribe(subscriber) => self.subscribe(subscriber),
+                DaemonEvent::StateChanged(id, state) => self.publish(id, state),
+                DaemonEvent::FocusChanged(window) => self.apply_profiles(&window),
+                DaemonEvent::Signal(signal) => {
+                    log::info!("Received {signal}, shutting down");
+                    self.shutdown();
+                    return Ok(());
+                }
+                DaemonEvent::Panic => {
+                    self.shutdown();
+                    return Err(anyhow!("An unexpected error occured"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reloads the running adapters without ungrabbing their devices, keeps the previous configuration on error
+    fn reload(&mut self) -> Result<()> {
+        let path = self.config_path.to_string_lossy().to_string();
+        let (config, files) = read_config_with_files(Some(path))?;
+
+        log::info!("Configuration reloaded");
+
+        // the included files may have changed
+        if let Some(watcher) = &self.config_watcher {
+            let _ = watcher.send(files.clone());
+        }
+
+        self.config = config;
+        self.config_files = files;
+        // the layers are reset by the adapters, the new watcher reapplies the profiles
+        self.profile_layers.clear();
+        self.spawn_focus_watcher();
+
+        let keyboards = &self.config.keyboards;
+        let defaults = &self.config.defaults;
+        let devices = &self.devices;
+
+        // adapters whose devices no longer match any keyboard are stopped
+        self.adapters.retain_mut(|adapter| {
+            let index = adapter.paths.first().and_then(|path| {
+                let info = devices.iter().find(|info| info.path == *path)?;
+
+                keyboards
+                    .iter()
+                    .position(|keyboard| matches_device(keyboard, info))
+            });
+
+            let Some(index) = index else {
+                return false;
+            };
+
+            let command =
+                AdapterCommand::Reload(Box::new(keyboards[index].clone()), defaults.clone());
+
+            adapter.keyboard = index;
+            adapter.sender.send(command).is_ok()
+        });
+
+        self.refresh_devices()
+    }
+
+    /// Lists the connected devices and attaches the matching ones
+    fn refresh_devices(&mut self) -> Result<()> {
+        let devices = find_input_devices()?;
+
+        self.devices = devices
+            .iter()
+            .map(|(path, device)| DeviceInfo::new(path, device))
+            .collect();
+
+        self.publish_devices();
+        self.attach(devices);
+
+        Ok(())
+    }
+
+    /// Sends the connected devices to the adapters, for the device activation conditions
+    fn publish_devices(&self) {
+        for adapter in &self.adapters {
+            let _ = adapter
+                .sender
+                .send(AdapterCommand::Devices(self.devices.clone()));
+        }
+    }
+
+    fn handle_request(&mut self, request: Request) -> Result<Vec<KeyboardState>> {
+        let (keyboard, command) = match request {
+            Request::Reload => return self.reload().map(|_| Vec::new()),
+            Request::Keyboards => (None, ControlCommand::State),
+            Request::Layers { keyboard } => (keyboard, ControlCommand::State),
+            Request::ActivateLayer { layer, keyboard } => {
+                (keyboard, ControlCommand::ActivateLayer(layer))
+            }
+            Request::DeactivateLayer { layer, keyboard } => {
+                (keyboard, ControlCommand::DeactivateLayer(layer))
+            }
+            Request::ToggleLayer { layer, keyboard } => {
+                (keyboard, ControlCommand::ToggleLayer(layer))
+            }
+            Request::Pause { keyboard } => (keyboard, ControlCommand::Pause),
+            Request::Resume { keyboard } => (keyboard, ControlCommand::Resume),
+            Request::Subscribe => return Err(anyhow!("Subscriptions must use a dedicated stream")),
+        };
+
+        let layer = match &command {
+            ControlCommand::ActivateLayer(layer)
+            | ControlCommand::DeactivateLayer(layer)
+            | ControlCommand::ToggleLayer(layer) => Some(layer),
+            _ => None,
+        };
+
+        let mut results = Vec::new();
+
+        for adapter in &self.adapters {
+            let config = &self.config.keyboards[adapter.keyboard];
+            let name = config.label(adapter.keyboard);
+
+            if keyboard.as_ref().is_some_and(|value| *value != name)
+                || layer.is_some_and(|layer| !config.layers.contains_key(layer))
+            {
+                continue;
+            }
+
+            let (reply, receiver) = mpsc::channel();
+
+            adapter
+                .sender
+                .send(AdapterCommand::Control(command.clone(), reply))?;
+
+            let state = receiver.recv_timeout(CONTROL_TIMEOUT)?;
+
+            results.push(KeyboardState {
+                name,
+                devices: adapter.paths.clone(),
+                layers: state.layers,
+                paused: state.paused,
+            });
+        }
+
+        if results.is_empty() && (keyboard.is_some() || layer.is_some()) {
+            return Err(anyhow!("No matching keyboard found"));
+        }
+
+        Ok(results)
+    }
+
+    /// Sends the current state of every keyboard, then keeps the subscriber for the next changes
+    fn subscribe(&mut self, subscriber: Sender<KeyboardState>) {
+        match self.handle_request(Request::Keyboards) {
+            Ok(keyboards) => {
+                if keyboards
+                    .into_iter()
+                    .all(|keyboard| subscriber.send(keyboard).is_ok())
+                {
+                    self.subscribers.push(subscriber);
+                }
+            }
+            Err(err) => log::error!("Failed to query the keyboard states: {err}"),
+        }
+    }
+
+    fn publish(&mut self, id: usize, state: AdapterState) {
+        let Some(adapter) = self.adapters.iter().find(|adapter| adapter.id == id) else {
+            return;
+        };
+
+        let keyboard = KeyboardState {
+            name: self.config.keyboards[adapter.keyboard].label(adapter.keyboard),
+            devices: adapter.paths.clone(),
+            layers: state.layers,
+            paused: state.paused,
+        };
+
+        // disconnected subscribers are dropped
+        self.subscribers
+            .retain(|subscriber| subscriber.send(keyboard.clone()).is_ok());
+    }
+
+    /// Activates the layers of the profiles matching the window and deactivates the previous ones
+    fn apply_profiles(&mut self, window: &WindowInfo) {
+        let Some(app_profiles) = &self.config.app_profiles else {
+            return;
+        };
+
+        let layers = profile_layers(&app_profiles.profiles, window);
+        let previous = mem::replace(&mut self.profile_layers, layers.clone());
+
+        log::debug!("Focused window: {window:?}, profile layers: {layers:?}");
+
+        let mut requests = previous
+            .into_iter()
+            .filter(|layer| !layers.contains(layer))
+            .map(|layer| Request::DeactivateLayer {
+                layer,
+                keyboard: None,
+            })
+            .collect::<Vec<_>>();
+
+        requests.extend(layers.into_iter().map(|layer| Request::ActivateLayer {
+            layer,
+            keyboard: None,
+        }));
+
+        for request in requests {
+            if let Err(err) = self.handle_request(request) {
+                log::debug!("Failed to apply the application profile: {err}");
+            }
+        }
+    }
+
+    /// Stops the adapters, which release the held keys and ungrab their devices
+    fn shutdown(&mut self) {
+        let mut pending = self
+            .adapters
+            .drain(..)
+            .map(|adapter| adapter.id)
+            .collect::<Vec<_>>();
+
+        while !pending.is_empty() {
+            match self.receiver.recv_timeout(SHUTDOWN_TIMEOUT) {
+                Ok(DaemonEvent::AdapterStopped(id)) => pending.retain(|value| *value != id),
+                Ok(_) => {}
+                Err(_) => {
+                    log::warn!("Some keyboards failed to stop in time");
+                    break;
+                }
+            }
+        }
+
+        if let Some(path) = &self.socket_path {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn spawn_signal_handler(&self) -> Result<()> {
+        let mut signals = SigSet::empty();
+
+        signals.add(Signal::SIGTERM);
+        signals.add(Signal::SIGINT);
+        signals.add(Signal::SIGHUP);
+        signals.thread_block()?;
+
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            while let Ok(signal) = signals.wait() {
+                if sender.send(DaemonEvent::Signal(signal)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn spawn_config_watcher(&mut self) {
+        let sender = self.sender.clone();
+        let files = self.config_files.clone();
+        let (watcher, updates) = mpsc::channel();
+
+        self.config_watcher = Some(watcher);
+
+        thread::spawn(move || {
+            let result = watch_files(files, &updates, || {
+                Ok(sender.send(DaemonEvent::ConfigChanged)?)
+            });
+
+            if let Err(err) = result {
+                log::error!("Failed to watch the configuration file: {err}");
+            }
+        });
+    }
+
+    fn spawn_control_server(&mut self) {
+        let (path, listener) = match bind_socket(self.config.control.group.as_deref()) {
+            Ok(value) => value,
+            Err(err) => {
+                log::error!("Failed to start the control server: {err}");
+                return;
+            }
+        };
+
+        let sender = self.sender.clone();
+
+        self.socket_path = Some(path);
+        thread::spawn(move || control::serve(listener, sender));
+    }
+
+    /// Replaces the running focus watcher
+    ///
+    /// The previous watcher can't be interrupted while it waits for the provider, it only stops
+    /// on its next focus change. Until then it keeps its connection open (e.g. the sway or
+    /// Hyprland socket), and the events it receives are dropped.
+    fn spawn_focus_watcher(&self) {
+        let generation = self.focus_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let Some(app_profiles) = &self.config.app_profiles else {
+            return;
+        };
+
+        let provider = app_profiles.provider.clone();
+        let sender = self.sender.clone();
+        let current = self.focus_generation.clone();
+        let is_current = move || current.load(Ordering::SeqCst) == generation;
+
+        thread::spawn(move || loop {
+            let result = watch_focus(&provider, |window| {
+                if !is_current() {
+                    return Err(anyhow!("Focus watcher replaced"));
+                }
+
+                Ok(sender.send(DaemonEvent::FocusChanged(window))?)
+            });
+
+            if !is_current() {
+                break;
+            }
+
+            if let Err(err) = result {
+                log::warn!("Focus provider unavailable, retrying: {err}");
+            }
+
+            // the window manager may start after okey or restart
+            thread::sleep(FOCUS_RETRY_DELAY);
+        });
+    }
+
+    fn install_panic_hook(&self) {
+        let sender = self.sender.clone();
+        let default_hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            default_hook(info);
+
+            // the main thread shuts down while unwinding (see `Drop`), the other threads (focus
+            // watcher, control clients, ...) can fail on their own
+            let is_adapter = thread::current()
+                .name()
+                .is_some_and(|name| name.starts_with(ADAPTER_THREAD_PREFIX));
+
+            if is_adapter {
+                let _ = sender.send(DaemonEvent::Panic);
+            }
+        }));
+    }
+
+    fn spawn_watcher(&self) {
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            let result = watch_input_devices(|event| Ok(sender.send(DaemonEvent::Device(event))?));
+
+            if let Err(err) = result {
+                log::error!("Failed to watch input devices: {err}");
+            }
+        });
+    }
+
+    fn attach(&mut self, devices: Vec<(PathBuf, Device)>) {
+        let mut groups: Vec<(usize, Vec<(PathBuf, Device)>)> = Vec::new();
+
+        for (path, device) in devices {
+            // udev events can be received several times for the same device
+            if self
+                .adapters
+                .iter()
+                .any(|adapter| adapter.paths.contains(&path))
+            {
+                continue;
+            }
+
+            let info = DeviceInfo::new(&path, &device);
+            let index = self
+                .config
+                .keyboards
+                .iter()
+                .position(|keyboard| matches_device(keyboard, &info));
+
+            let Some(index) = index else {
+                continue;
+            };
+
+            log::info!(
+                "Device connected: {} ({})",
+                device.name().unwrap_or("Unknown device"),
+                path.display()
+            );
+
+            let merge = self.config.keyboards[index].merge;
+
+            let device = match merge {
+                true => match self.merge_device(index, &path, device) {
+                    Some(device) => device,
+                    None => continue,
+                },
+                false => device,
+            };
+
+            match groups
+                .iter_mut()
+                .find(|(value, _)| merge && *value == index)
+            {
+                Some((_, group)) => group.push((path, device)),
+                None => groups.push((index, vec![(path, device)])),
+            }
+        }
+
+        if groups.is_empty() {
+            return;
+        }
+
+        for (index, devices) in groups {
+            self.spawn_adapter(index, devices);
+        }
+
+        // new keyboards follow the focused application as well
+        for layer in self.profile_layers.clone() {
+            let _ = self.handle_request(Request::ActivateLayer {
+                layer,
+                keyboard: None,
+            });
+        }
+    }
+
+    /// Adds the device to the running adapter of the keyboard, returns it back if there is none
+    fn merge_device(&mut self, index: usize, path: &Path, device: Device) -> Option<Device> {
+        let Some(adapter) = self
+            .adapters
+            .iter_mut()
+            .find(|value| value.keyboard == index)
+        else {
+            return Some(device);
+        };
+
+        if adapter
+            .sender
+            .send(AdapterCommand::AddDevice(Box::new(device)))
+            .is_ok()
+        {
+            adapter.paths.push(path.to_path_buf());
+            return None;
+        }
+
+        // the adapter has stopped in the meantime
+        open_input_device(path)
+    }
+
+    fn spawn_adapter(&mut self, index: usize, devices: Vec<(PathBuf, Device)>) {
+        let (paths, devices) = devices.into_iter().unzip();
+        let (sender, receiver) = mpsc::channel();
+        let keyboard = self.config.keyboards[index].clone();
+        let defaults = self.config.defaults.clone();
+        let events = self.sender.clone();
+        let id = self.next_id;
+
+        self.next_id += 1;
+
+        let _ = sender.send(AdapterCommand::Devices(self.devices.clone()));
+
+        let result = thread::Builder::new()
+            .name(format!("{ADAPTER_THREAD_PREFIX}{id}"))
+            .spawn(move || {
+                let label = keyboard.label(index);
+
+                // the proxy is dropped while unwinding, releasing the keys and the devices
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    hook_devices(keyboard, defaults, devices, &receiver, |state| {
+                        let _ = events.send(DaemonEvent::StateChanged(id, state.clone()));
+                    })
+                }));
+
+                if let Ok(Err(err)) = result {
+                    log::error!("{label}: {err}");
+                }
+
+                let _ = events.send(DaemonEvent::AdapterStopped(id));
+            });
+
+        match result {
+            Ok(_) => self.adapters.push(AdapterHandle {
+                id,
+                keyboard: index,
+                paths,
+                sender,
+            }),
+            Err(err) => log::error!("Failed to start the adapter: {err}"),
+        }
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        // the adapters stop once their command channel is closed, their proxies release the held
+        // keys and ungrab the devices when dropped
+        if thread::panicking() {
+            self.shutdown();
+        }
+    }
+}
+
+fn hook_devices(
+    keyboard: KeyboardConfig,
+    defaults: DefaultConfig,
+    devices: Vec<Device>,
+    commands: &Receiver<AdapterCommand>,
+    on_change: impl FnMut(&AdapterState),
+) -> Result<()> {
+    let mut capabilities = Capabilities::default();
+
+    if keyboard.wheel_keys {
+        capabilities.insert_wheel();
+    }
+
+    let mut proxy = InputProxy::try_from_devices(devices, capabilities)?;
+    let mut adapter = KeyAdapter::new(keyboard, defaults, &mut proxy);
+
+    adapter.hook(commands, on_change)
+}
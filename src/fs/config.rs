@@ -1,9 +1,49 @@
-use std::{env, fs, io, path::Path};
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsStr,
+    fmt, fs, io, mem,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
 
 use anyhow::{Result, anyhow};
-use nix::unistd;
+use nix::{
+    sys::{
+        epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags},
+        inotify::{AddWatchFlags, InitFlags, Inotify, WatchDescriptor},
+    },
+    unistd,
+};
+use serde::Deserializer;
+use serde_path_to_error::Segment;
+use serde_yaml::{Mapping, Value};
 
-use crate::config::schema::Config;
+use crate::config::{lint::lint_keycodes, schema::Config};
+
+/// Configuration files probed in the configuration directory, in order
+const CONFIG_FILE_NAMES: [&str; 3] = ["config.yaml", "config.toml", "config.json"];
+
+/// Delay after which the watcher checks for an updated list of files
+const WATCH_UPDATE_TIMEOUT: u16 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Files without a known extension are parsed as YAML
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+}
 
 pub fn get_config_dir_path() -> Result<String> {
     if unistd::geteuid().is_root() {
@@ -19,13 +59,27 @@ pub fn get_config_dir_path() -> Result<String> {
 
 pub fn get_default_config_path() -> Result<String> {
     let config_dir_path = get_config_dir_path()?;
-    let default_path = Path::new(&config_dir_path).join("config.yaml");
+    let default_path = find_config_file(Path::new(&config_dir_path));
     let default_path_str = default_path.to_string_lossy().to_string();
 
     Ok(default_path_str)
 }
 
+/// Returns the first existing configuration file of the directory, `config.yaml` otherwise
+pub fn find_config_file(dir: &Path) -> PathBuf {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join(CONFIG_FILE_NAMES[0]))
+}
+
 pub fn read_config(path: Option<String>) -> Result<Config> {
+    read_config_with_files(path).map(|(config, _)| config)
+}
+
+/// Reads the configuration along with the list of files it was read from, includes included
+pub fn read_config_with_files(path: Option<String>) -> Result<(Config, Vec<PathBuf>)> {
     let config_path = path
         .unwrap_or_else(|| get_default_config_path().expect("Failed to get default config path"));
 
@@ -34,7 +88,448 @@ pub fn read_config(path: Option<String>) -> Result<Config> {
             io::ErrorKind::NotFound => anyhow!("Configuration file not found at {config_path}"),
             _ => err.into(),
         })
-        .and_then(|config| Ok(serde_yaml::from_str(&config)?))?;
+        .and_then(|config| parse_config_file(&config, Path::new(&config_path)))?;
+
+    for lint in lint_keycodes(&parsed.0) {
+        log::warn!("{}: {}", lint.keyboard, lint.message);
+    }
 
     Ok(parsed)
 }
+
+/// Parses the configuration, errors include the path of the invalid field
+pub fn parse_config(source: &str, format: ConfigFormat) -> Result<Config> {
+    match format {
+        ConfigFormat::Yaml => deserialize_config(serde_yaml::Deserializer::from_str(source)),
+        ConfigFormat::Toml => deserialize_config(toml::Deserializer::parse(source)?),
+        ConfigFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_str(source);
+            let config = deserialize_config(&mut deserializer)?;
+
+            deserializer.end()?;
+
+            Ok(config)
+        }
+    }
+}
+
+/// Parses a document of any format into a YAML value, to be merged with other fragments
+fn parse_document(source: &str, format: ConfigFormat) -> Result<Value> {
+    let document = match format {
+        ConfigFormat::Yaml => serde_yaml::from_str(source)?,
+        ConfigFormat::Toml => toml::from_str(source)?,
+        ConfigFormat::Json => serde_json::from_str(source)?,
+    };
+
+    Ok(document)
+}
+
+/// Parses a configuration file after resolving its includes and templates, returns the included files as well
+fn parse_config_file(source: &str, path: &Path) -> Result<(Config, Vec<PathBuf>)> {
+    let format = ConfigFormat::from_path(path);
+    let mut document = parse_document(source, format)?;
+
+    // errors only have a line and a column when parsing the source directly
+    if !has_fragments(&document) {
+        return Ok((parse_config(source, format)?, vec![path.to_path_buf()]));
+    }
+
+    let root = fs::canonicalize(path)?;
+
+    let mut includes = Includes {
+        stack: vec![(root.clone(), 0)],
+        fragments: vec![(root, Fragment::Config, document.clone())],
+    };
+
+    resolve_includes(&mut document, path, Fragment::Config, &mut includes)?;
+    resolve_templates(&mut document)?;
+
+    let config = try_deserialize_config(document).map_err(|err| {
+        let keys = err.keys();
+
+        let origin = includes
+            .fragments
+            .iter()
+            .find(|(_, kind, fragment)| defines_field(fragment, *kind, &keys))
+            .map_or(path, |(origin, _, _)| origin);
+
+        anyhow!("{}: {err}", origin.display())
+    })?;
+
+    let mut files = includes
+        .fragments
+        .into_iter()
+        .map(|(path, _, _)| path)
+        .collect::<Vec<_>>();
+
+    files.sort();
+    files.dedup();
+
+    Ok((config, files))
+}
+
+fn deserialize_config<'de, D>(deserializer: D) -> Result<Config>
+where
+    D: Deserializer<'de>,
+{
+    try_deserialize_config(deserializer).map_err(|err| anyhow!("{err}"))
+}
+
+fn try_deserialize_config<'de, D>(deserializer: D) -> Result<Config, DeserializeError>
+where
+    D: Deserializer<'de>,
+{
+    serde_path_to_error::deserialize(deserializer).map_err(|err| {
+        let path = err.path().clone();
+        let message = err.inner().to_string();
+
+        // serde_yaml only knows the path up to the closest untagged enum
+        let message = match message.split_once(": ") {
+            Some((prefix, rest)) if path.to_string().starts_with(prefix) => rest.to_string(),
+            _ => message,
+        };
+
+        DeserializeError { path, message }
+    })
+}
+
+#[derive(Debug)]
+struct DeserializeError {
+    path: serde_path_to_error::Path,
+    message: String,
+}
+
+impl DeserializeError {
+    fn keys(&self) -> Vec<FieldKey> {
+        self.path
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Seq { .. } => Some(FieldKey::Any),
+                Segment::Map { key } => Some(FieldKey::Name(key.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.to_string().as_str() {
+            "." => write!(f, "{}", self.message),
+            path => write!(f, "{path}: {}", self.message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldKey {
+    Name(String),
+    Any,
+}
+
+/// Whether the fragment, before being merged, contains the field at the given path
+fn defines_field(fragment: &Value, kind: Fragment, keys: &[FieldKey]) -> bool {
+    let keyboard_keys = match keys {
+        [FieldKey::Name(name), FieldKey::Any, rest @ ..] if name == "keyboards" => Some(rest),
+        [FieldKey::Name(name), FieldKey::Name(_), rest @ ..] if name == "templates" => Some(rest),
+        _ => None,
+    };
+
+    match (kind, keyboard_keys) {
+        (Fragment::Config, Some(rest)) => {
+            let templates = [FieldKey::Name("templates".to_string()), FieldKey::Any];
+            contains_field(fragment, keys) || contains_field(fragment, &[&templates, rest].concat())
+        }
+        (Fragment::Config, None) => contains_field(fragment, keys),
+        (Fragment::Keyboard, Some(rest)) => contains_field(fragment, rest),
+        (Fragment::Keyboard, None) => false,
+    }
+}
+
+fn contains_field(value: &Value, keys: &[FieldKey]) -> bool {
+    match keys.split_first() {
+        None => true,
+        Some((FieldKey::Name(name), rest)) => value
+            .get(name.as_str())
+            .is_some_and(|value| contains_field(value, rest)),
+        Some((FieldKey::Any, rest)) => match value {
+            Value::Sequence(items) => items.iter().any(|item| contains_field(item, rest)),
+            Value::Mapping(entries) => entries.values().any(|entry| contains_field(entry, rest)),
+            _ => false,
+        },
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Fragment {
+    Config,
+    Keyboard,
+}
+
+/// Files being included, along with every fragment loaded so far
+struct Includes {
+    /// Included files with the index of their fragment
+    stack: Vec<(PathBuf, usize)>,
+    /// Fragments ordered by precedence, a file overrides its includes and the later includes
+    fragments: Vec<(PathBuf, Fragment, Value)>,
+}
+
+fn has_fragments(document: &Value) -> bool {
+    let keyboards = document
+        .get("keyboards")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten();
+
+    document.get("include").is_some()
+        || document.get("templates").is_some()
+        || keyboards
+            .into_iter()
+            .any(|keyboard| keyboard.get("include").is_some() || keyboard.get("extends").is_some())
+}
+
+/// Replaces the `include` list of a configuration or a keyboard by the content of the included files
+fn resolve_includes(
+    document: &mut Value,
+    path: &Path,
+    kind: Fragment,
+    includes: &mut Includes,
+) -> Result<()> {
+    let Value::Mapping(mapping) = document else {
+        return Ok(());
+    };
+
+    if let Fragment::Config = kind {
+        if let Some(Value::Sequence(keyboards)) = mapping.get_mut("keyboards") {
+            for keyboard in keyboards {
+                resolve_includes(keyboard, path, Fragment::Keyboard, includes)?;
+            }
+        }
+
+        if let Some(Value::Mapping(templates)) = mapping.get_mut("templates") {
+            for template in templates.values_mut() {
+                resolve_includes(template, path, Fragment::Keyboard, includes)?;
+            }
+        }
+    }
+
+    let Some(files) = mapping.remove("include") else {
+        return Ok(());
+    };
+
+    let files = serde_yaml::from_value::<Vec<PathBuf>>(files)
+        .map_err(|err| anyhow!("{}: include: {err}", path.display()))?;
+
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let mut merged = Mapping::new();
+
+    for file in files {
+        let fragment = load_fragment(&directory.join(file), kind, includes)?;
+        merge_fragment(&mut merged, fragment);
+    }
+
+    merge_fragment(&mut merged, mem::take(mapping));
+    *mapping = merged;
+
+    Ok(())
+}
+
+fn load_fragment(path: &Path, kind: Fragment, includes: &mut Includes) -> Result<Mapping> {
+    let path = fs::canonicalize(path)
+        .map_err(|err| anyhow!("Failed to include {}: {err}", path.display()))?;
+
+    if let Some(index) = includes
+        .stack
+        .iter()
+        .position(|(included, _)| *included == path)
+    {
+        let cycle = includes.stack[index..]
+            .iter()
+            .map(|(path, _)| path)
+            .chain([&path])
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+
+        return Err(anyhow!("Include cycle detected: {}", cycle.join(" -> ")));
+    }
+
+    let source = fs::read_to_string(&path)?;
+    let mut fragment = parse_document(&source, ConfigFormat::from_path(&path))
+        .map_err(|err| anyhow!("{}: {err}", path.display()))?;
+
+    let index = includes.stack.last().map_or(0, |(_, parent)| parent + 1);
+
+    includes
+        .fragments
+        .insert(index, (path.clone(), kind, fragment.clone()));
+    includes.stack.push((path.clone(), index));
+    resolve_includes(&mut fragment, &path, kind, includes)?;
+    includes.stack.pop();
+
+    match fragment {
+        Value::Mapping(mapping) => Ok(mapping),
+        Value::Null => Ok(Mapping::new()),
+        _ => Err(anyhow!("{}: expected a mapping", path.display())),
+    }
+}
+
+/// Merges the keyboard templates listed in `extends` into the keyboards
+fn resolve_templates(document: &mut Value) -> Result<()> {
+    let templates = document
+        .get("templates")
+        .and_then(Value::as_mapping)
+        .cloned()
+        .unwrap_or_default();
+
+    let keyboards = document
+        .get_mut("keyboards")
+        .and_then(Value::as_sequence_mut)
+        .into_iter()
+        .flatten();
+
+    for keyboard in keyboards {
+        if let Value::Mapping(keyboard) = keyboard {
+            apply_templates(keyboard, &templates, &mut Vec::new())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_templates(
+    keyboard: &mut Mapping,
+    templates: &Mapping,
+    stack: &mut Vec<String>,
+) -> Result<()> {
+    let names = match keyboard.get("extends") {
+        Some(Value::String(name)) => vec![name.clone()],
+        Some(names) => serde_yaml::from_value::<Vec<String>>(names.clone())
+            .map_err(|err| anyhow!("extends: {err}"))?,
+        None => return Ok(()),
+    };
+
+    let mut merged = Mapping::new();
+
+    for name in names {
+        if stack.contains(&name) {
+            let cycle = stack.iter().chain([&name]).cloned().collect::<Vec<_>>();
+            return Err(anyhow!("Template cycle detected: {}", cycle.join(" -> ")));
+        }
+
+        let mut template = match templates.get(&name) {
+            Some(Value::Mapping(template)) => template.clone(),
+            Some(_) => return Err(anyhow!("Template {name} must be a mapping")),
+            None => return Err(anyhow!("Unknown template: {name}")),
+        };
+
+        stack.push(name);
+        apply_templates(&mut template, templates, stack)?;
+        stack.pop();
+
+        // the keyboard only keeps its own extends list
+        template.remove("extends");
+        merge_fragment(&mut merged, template);
+    }
+
+    merge_fragment(&mut merged, mem::take(keyboard));
+    *keyboard = merged;
+
+    Ok(())
+}
+
+/// Maps are merged entry by entry, lists are appended and other values are replaced
+fn merge_fragment(base: &mut Mapping, fragment: Mapping) {
+    for (key, value) in fragment {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Mapping(entries)), Value::Mapping(other)) => entries.extend(other),
+            (Some(Value::Sequence(items)), Value::Sequence(other)) => items.extend(other),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Calls `callback` each time the configuration file is written
+pub fn watch_config<F>(path: &Path, callback: F) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let (_sender, updates) = mpsc::channel();
+    watch_files(vec![path.to_path_buf()], &updates, callback)
+}
+
+/// Calls `callback` each time one of the files is written, the files can be replaced through `updates`
+pub fn watch_files<F>(
+    files: Vec<PathBuf>,
+    updates: &Receiver<Vec<PathBuf>>,
+    mut callback: F,
+) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let inotify = Inotify::init(InitFlags::IN_CLOEXEC)?;
+    let epoll = Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC)?;
+    let mut watches = HashMap::new();
+    let mut files = update_watches(&inotify, &mut watches, &files)?;
+
+    epoll.add(&inotify, EpollEvent::new(EpollFlags::EPOLLIN, 0))?;
+
+    loop {
+        if let Some(value) = updates.try_iter().last() {
+            files = update_watches(&inotify, &mut watches, &value)?;
+        }
+
+        if epoll.wait(&mut [EpollEvent::empty()], WATCH_UPDATE_TIMEOUT)? == 0 {
+            continue;
+        }
+
+        let is_modified = inotify.read_events()?.iter().any(|event| {
+            let dir = watches.get(&event.wd);
+            let path = dir
+                .zip(event.name.as_ref())
+                .map(|(dir, name)| dir.join(name));
+
+            path.is_some_and(|path| files.contains(&path))
+        });
+
+        if is_modified {
+            callback()?;
+        }
+    }
+}
+
+/// Watches the parent directories of the files, returns the paths of the files to look for
+fn update_watches(
+    inotify: &Inotify,
+    watches: &mut HashMap<WatchDescriptor, PathBuf>,
+    files: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    for (descriptor, _) in watches.drain() {
+        let _ = inotify.rm_watch(descriptor);
+    }
+
+    // editors often replace the file instead of writing to it
+    let flags = AddWatchFlags::IN_CLOSE_WRITE | AddWatchFlags::IN_MOVED_TO;
+    let mut paths = Vec::new();
+
+    for file in files {
+        let dir = file
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        // the events only contain the file name relative to the watched directory
+        let dir = fs::canonicalize(dir)?;
+
+        if let Some(name) = file.file_name() {
+            paths.push(dir.join(name));
+        }
+
+        if !watches.values().any(|value| *value == dir) {
+            watches.insert(inotify.add_watch(&dir, flags)?, dir);
+        }
+    }
+
+    Ok(paths)
+}
@@ -1,35 +1,219 @@
-use std::fs;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Result;
-use evdev::Device;
+use evdev::{BusType, Device, InputId};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
 
-pub fn find_device_by_name(name: &str) -> Result<Option<Device>> {
-    let devices = find_input_devices()?;
+use crate::{
+    config::schema::{DeviceMatcher, KeyboardConfig, Pattern},
+    core::{VIRTUAL_NAME_SUFFIX, VIRTUAL_PRODUCT, VIRTUAL_VENDOR},
+};
 
-    let device = devices
-        .into_iter()
-        .find(|dev| dev.name().is_some_and(|value| value == name));
+const INPUT_DIR: &str = "/dev/input";
+const ALIAS_DIRS: [&str; 2] = ["/dev/input/by-id", "/dev/input/by-path"];
 
-    Ok(device)
-}
-
-pub fn find_input_devices() -> Result<Vec<Device>> {
-    let files = fs::read_dir("/dev/input")?;
+pub fn find_input_devices() -> Result<Vec<(PathBuf, Device)>> {
+    let files = fs::read_dir(INPUT_DIR)?;
     let mut results = Vec::new();
 
     for file in files {
         let entry = file?;
 
-        if !entry.file_name().to_string_lossy().contains("event") {
-            continue;
+        if let Some(device) = open_input_device(&entry.path()) {
+            results.push((entry.path(), device));
+        }
+    }
+
+    Ok(results)
+}
+
+pub fn open_input_device(path: &Path) -> Option<Device> {
+    let is_event_file = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with("event"));
+
+    if !is_event_file {
+        return None;
+    }
+
+    Device::open(path)
+        .ok()
+        .filter(|device| device.supported_keys().is_some())
+}
+
+/// Identifying properties of an input device, collected once so that matching doesn't need any I/O
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    pub path: PathBuf,
+    /// The device node path along with the udev symlinks pointing to it
+    pub aliases: Vec<PathBuf>,
+    pub name: Option<String>,
+    pub phys: Option<String>,
+    pub uniq: Option<String>,
+    pub id: InputId,
+}
+
+impl DeviceInfo {
+    pub fn new(path: &Path, device: &Device) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            aliases: find_device_aliases(path),
+            name: device.name().map(String::from),
+            phys: device.physical_path().map(String::from),
+            uniq: device.unique_name().map(String::from),
+            id: device.input_id(),
         }
+    }
+
+    /// Whether the device is one of the virtual devices created by okey
+    pub fn is_virtual(&self) -> bool {
+        self.id.bus_type() == BusType::BUS_VIRTUAL
+            && self.id.vendor() == VIRTUAL_VENDOR
+            && self.id.product() == VIRTUAL_PRODUCT
+            && self
+                .name
+                .as_ref()
+                .is_some_and(|name| name.ends_with(VIRTUAL_NAME_SUFFIX))
+    }
+}
+
+pub fn matches_device(keyboard: &KeyboardConfig, info: &DeviceInfo) -> bool {
+    // grabbing our own output would create a feedback loop
+    if info.is_virtual() {
+        return false;
+    }
+
+    let Some(config) = &keyboard.matcher else {
+        return info.name.as_deref() == Some(keyboard.name.as_str());
+    };
 
-        if let Ok(device) = Device::open(entry.path()) {
-            if device.supported_keys().is_some() {
-                results.push(device);
+    config
+        .matchers()
+        .iter()
+        .any(|matcher| matches_properties(matcher, info))
+}
+
+pub fn is_device_connected(matcher: &DeviceMatcher, devices: &[DeviceInfo]) -> bool {
+    devices
+        .iter()
+        .any(|info| !info.is_virtual() && matches_properties(matcher, info))
+}
+
+fn matches_properties(matcher: &DeviceMatcher, info: &DeviceInfo) -> bool {
+    matches_pattern(&matcher.name, info.name.as_deref())
+        && matches_pattern(&matcher.phys, info.phys.as_deref())
+        && matches_pattern(&matcher.uniq, info.uniq.as_deref())
+        && matcher
+            .vendor
+            .is_none_or(|vendor| vendor == info.id.vendor())
+        && matcher
+            .product
+            .is_none_or(|product| product == info.id.product())
+        && matcher.path.as_ref().is_none_or(|pattern| {
+            info.aliases
+                .iter()
+                .any(|path| pattern.is_match(&path.to_string_lossy()))
+        })
+}
+
+fn matches_pattern(pattern: &Option<Pattern>, value: Option<&str>) -> bool {
+    pattern
+        .as_ref()
+        .is_none_or(|pattern| pattern.is_match(value.unwrap_or_default()))
+}
+
+/// Returns the device node path along with the udev symlinks pointing to it
+pub fn find_device_aliases(path: &Path) -> Vec<PathBuf> {
+    let mut results = vec![path.to_path_buf()];
+
+    for dir in ALIAS_DIRS {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            if fs::canonicalize(entry.path()).is_ok_and(|target| target == path) {
+                results.push(entry.path());
             }
         }
     }
 
-    Ok(results)
+    results
+}
+
+#[derive(Debug)]
+pub enum DeviceEvent {
+    Added(PathBuf),
+    Removed(PathBuf),
+}
+
+/// Calls `callback` for each input device node created, updated or removed by udev, and for each new symlink to it
+pub fn watch_input_devices<F>(mut callback: F) -> Result<()>
+where
+    F: FnMut(DeviceEvent) -> Result<()>,
+{
+    let inotify = Inotify::init(InitFlags::IN_CLOEXEC)?;
+
+    // udev sets the device permissions after the node is created
+    let flags = AddWatchFlags::IN_CREATE | AddWatchFlags::IN_ATTRIB | AddWatchFlags::IN_DELETE;
+    let input_dir = inotify.add_watch(INPUT_DIR, flags)?;
+    let mut alias_dirs = Vec::new();
+
+    for dir in ALIAS_DIRS {
+        // the directories are missing until a device has a symlink
+        if let Ok(wd) = inotify.add_watch(dir, AddWatchFlags::IN_CREATE) {
+            alias_dirs.push((wd, dir));
+        }
+    }
+
+    loop {
+        for event in inotify.read_events()? {
+            let Some(name) = event.name else {
+                continue;
+            };
+
+            if event.wd != input_dir {
+                let dir = alias_dirs.iter().find(|(wd, _)| *wd == event.wd);
+
+                // the symlinks are created after the device node, `path` matchers may match now
+                if let Some((_, dir)) = dir {
+                    if let Ok(path) = fs::canonicalize(Path::new(dir).join(name)) {
+                        callback(DeviceEvent::Added(path))?;
+                    }
+                }
+
+                continue;
+            }
+
+            let path = Path::new(INPUT_DIR).join(name);
+
+            if event.mask.contains(AddWatchFlags::IN_ISDIR) {
+                let dir = ALIAS_DIRS.into_iter().find(|dir| path == Path::new(dir));
+
+                if let Some(dir) = dir.filter(|_| event.mask.contains(AddWatchFlags::IN_CREATE)) {
+                    let wd = inotify.add_watch(dir, AddWatchFlags::IN_CREATE)?;
+
+                    alias_dirs.retain(|(_, value)| *value != dir);
+                    alias_dirs.push((wd, dir));
+
+                    // the symlinks created before the watch was added
+                    for entry in fs::read_dir(dir)?.flatten() {
+                        if let Ok(path) = fs::canonicalize(entry.path()) {
+                            callback(DeviceEvent::Added(path))?;
+                        }
+                    }
+                }
+
+                continue;
+            }
+
+            match event.mask.contains(AddWatchFlags::IN_DELETE) {
+                true => callback(DeviceEvent::Removed(path))?,
+                false => callback(DeviceEvent::Added(path))?,
+            }
+        }
+    }
 }
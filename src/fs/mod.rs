@@ -1,3 +1,4 @@
 pub mod config;
 pub mod device;
 pub mod service;
+pub mod socket;
@@ -0,0 +1,80 @@
+use std::{
+    env,
+    fs::{self, Permissions},
+    io,
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use nix::unistd::{self, Group};
+
+const SOCKET_NAME: &str = "okey.sock";
+const SYSTEM_SOCKET_DIR: &str = "/run/okey";
+const SYSTEM_SOCKET_MODE: u32 = 0o666;
+const GROUP_SOCKET_MODE: u32 = 0o660;
+
+/// Returns the socket paths by priority, the system one is used when running as a service
+pub fn get_socket_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if let Ok(dir) = env::var("XDG_RUNTIME_DIR") {
+        paths.push(Path::new(&dir).join(SOCKET_NAME));
+    }
+
+    paths.push(Path::new(SYSTEM_SOCKET_DIR).join(SOCKET_NAME));
+    paths
+}
+
+pub fn find_socket_path() -> Option<PathBuf> {
+    get_socket_paths().into_iter().find(|path| path.exists())
+}
+
+/// Binds the control socket, the system one is restricted to `group` if any
+pub fn bind_socket(group: Option<&str>) -> Result<(PathBuf, UnixListener)> {
+    let path = get_socket_paths().remove(0);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    if UnixStream::connect(&path).is_ok() {
+        return Err(anyhow!(
+            "Another instance is listening on {}",
+            path.display()
+        ));
+    }
+
+    // the socket of a previous instance that didn't exit cleanly
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+
+    let listener = UnixListener::bind(&path)?;
+
+    // the user socket is already private to the session
+    if path.starts_with(SYSTEM_SOCKET_DIR) {
+        set_socket_access(&path, group)?;
+    }
+
+    Ok((path, listener))
+}
+
+/// Lets the users of the session reach the socket of the system service
+fn set_socket_access(path: &Path, group: Option<&str>) -> Result<()> {
+    let mode = match group {
+        Some(name) => {
+            let group = Group::from_name(name)?.ok_or(anyhow!("Unknown group: {name}"))?;
+
+            unistd::chown(path, None, Some(group.gid))?;
+            GROUP_SOCKET_MODE
+        }
+        None => SYSTEM_SOCKET_MODE,
+    };
+
+    Ok(fs::set_permissions(path, Permissions::from_mode(mode))?)
+}
@@ -2,6 +2,7 @@ pub mod cli;
 
 mod config;
 mod core;
+mod daemon;
 mod fs;
 
 #[cfg(test)]
@@ -15,6 +15,10 @@ fn main() -> Result<()> {
             false => commands::start::start(config),
         },
 
+        Command::Check { config } => commands::check::check(config),
+
+        Command::Schema => commands::schema::print_schema(),
+
         Command::Service { command } => match command {
             SystemdSubcommand::Start => commands::service::start(),
             SystemdSubcommand::Restart => commands::service::restart(),
@@ -25,7 +29,13 @@ fn main() -> Result<()> {
         },
 
         Command::Device { command } => match command {
-            DeviceSubcommand::List { keyboard } => commands::device::list(keyboard),
+            DeviceSubcommand::List { keyboard, pointer } => {
+                commands::device::list(keyboard, pointer)
+            }
         },
+
+        Command::Ctl { command } => commands::ctl::send(command.into()),
+
+        Command::Watch { format, keyboard } => commands::watch::watch(format, keyboard),
     }
 }
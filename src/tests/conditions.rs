@@ -0,0 +1,141 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use evdev::{BusType, InputId};
+
+use crate::{
+    config::schema::{TimeOfDay, TimeWindow},
+    fs::device::DeviceInfo,
+};
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/conditions.yaml");
+
+#[test]
+fn test_file_condition() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("okey-flag-{}", std::process::id()));
+    let config = CONFIG.replace("FLAG_FILE", &path.to_string_lossy());
+
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(&config, &mut proxy);
+    let now = Instant::now();
+
+    adapter.process_conditions(now);
+    assert!(!adapter.state().layers.contains(&"flagged".to_string()));
+
+    fs::write(&path, "")?;
+
+    // conditions are only checked once per second
+    adapter.process_conditions(now + Duration::from_millis(500));
+    assert!(!adapter.state().layers.contains(&"flagged".to_string()));
+
+    adapter.process_conditions(now + Duration::from_secs(1));
+    assert!(adapter.state().layers.contains(&"flagged".to_string()));
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_J)])?;
+
+    fs::remove_file(&path)?;
+
+    adapter.process_conditions(now + Duration::from_secs(2));
+    assert!(!adapter.state().layers.contains(&"flagged".to_string()));
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_J)])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_DOWN),
+        InputSequence::Tap(KeyCode::KEY_J),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_device_condition() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+    let now = Instant::now();
+
+    adapter.process_conditions(now);
+    assert!(!adapter.state().layers.contains(&"docked".to_string()));
+
+    let dock = DeviceInfo {
+        path: "/dev/input/event5".into(),
+        aliases: Vec::new(),
+        name: Some("Dock keyboard".to_string()),
+        phys: None,
+        uniq: None,
+        id: InputId::new(BusType::BUS_USB, 0x046d, 0xc31c, 0),
+    };
+
+    // the conditions are checked again as soon as the devices change
+    adapter.set_devices(vec![dock]);
+    adapter.process_conditions(now);
+    assert!(adapter.state().layers.contains(&"docked".to_string()));
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_K)])?;
+
+    adapter.set_devices(Vec::new());
+    adapter.process_conditions(now);
+    assert!(!adapter.state().layers.contains(&"docked".to_string()));
+
+    let expected = InputBuffer::new([InputSequence::Tap(KeyCode::KEY_UP)]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_time_window() {
+    let time = |value: &str| serde_yaml::from_str::<TimeOfDay>(value).unwrap();
+    let window = |from, to| TimeWindow {
+        from: time(from),
+        to: time(to),
+    };
+
+    let office = window("09:00", "17:30");
+
+    assert!(office.contains(time("09:00")));
+    assert!(office.contains(time("12:15")));
+    assert!(!office.contains(time("17:30")));
+    assert!(!office.contains(time("08:59")));
+
+    let night = window("22:00", "07:00");
+
+    assert!(night.contains(time("23:30")));
+    assert!(night.contains(time("00:00")));
+    assert!(night.contains(time("06:59")));
+    assert!(!night.contains(time("07:00")));
+    assert!(!night.contains(time("12:00")));
+
+    assert_eq!(time("7:05"), TimeOfDay(425));
+    assert!(serde_yaml::from_str::<TimeOfDay>("7:5").is_err());
+    assert!(serde_yaml::from_str::<TimeOfDay>("24:00").is_err());
+    assert!(serde_yaml::from_str::<TimeOfDay>("noon").is_err());
+}
+
+#[test]
+fn test_time_of_day_schema() {
+    let schema = serde_json::to_value(schemars::schema_for!(TimeOfDay)).unwrap();
+    let hours = ["0", "7", "07", "19", "23", "24", "007", "+7", " 7"];
+    let minutes = ["5", "05", "59", "60", "005", "+5"];
+
+    // the parser should accept exactly what the schema accepts
+    for hour in hours {
+        for minute in minutes {
+            let value = format!("{hour}:{minute}");
+            let parsed = serde_yaml::from_str::<TimeOfDay>(&format!("'{value}'"));
+
+            assert_eq!(
+                parsed.is_ok(),
+                jsonschema::draft7::is_valid(&schema, &value.into()),
+                "{hour}:{minute}"
+            );
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use super::utils::*;
+
+use crate::fs::config::{find_config_file, parse_config, read_config, ConfigFormat};
+
+const FORMAT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/config/formats");
+
+#[test]
+fn test_toml_config() -> Result<()> {
+    let mut config = read_config(Some(format!("{FORMAT_DIR}/config.toml")))?;
+    let keyboard = config.keyboards.remove(0);
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::new(keyboard, config.defaults, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_Q),
+        InputSequence::Hold(KeyCode::KEY_CAPSLOCK),
+        InputSequence::Tap(KeyCode::KEY_H),
+        InputSequence::Release(KeyCode::KEY_CAPSLOCK),
+        InputSequence::Tap(KeyCode::KEY_Z),
+    ])?;
+
+    adapter.wait_macros()?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_W),
+        InputSequence::Tap(KeyCode::KEY_LEFT),
+        InputSequence::Tap(KeyCode::KEY_X),
+        InputSequence::Tap(KeyCode::KEY_C),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_json_config() -> Result<()> {
+    let mut config = read_config(Some(format!("{FORMAT_DIR}/config.json")))?;
+    let keyboard = config.keyboards.remove(0);
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::new(keyboard, config.defaults, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_Q), // overrides the template
+        InputSequence::Hold(KeyCode::KEY_CAPSLOCK), // included YAML template
+        InputSequence::Tap(KeyCode::KEY_H),
+        InputSequence::Release(KeyCode::KEY_CAPSLOCK),
+    ])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_E),
+        InputSequence::Tap(KeyCode::KEY_LEFT),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_config_format_error_path() {
+    let config = r#"
+[[keyboards]]
+name = "Invalid keyboard"
+
+[keyboards.tap_dances.KEY_A]
+tap = "KEY_A"
+hold = { delay = "long" }
+"#;
+
+    let error = parse_config(config, ConfigFormat::Toml)
+        .unwrap_err()
+        .to_string();
+
+    assert_eq!(
+        error,
+        "keyboards[0].tap_dances.KEY_A.hold: TOML parse error at line 7, column 8
+  |
+7 | hold = { delay = \"long\" }
+  |        ^^^^^^^^^^^^^^^^^^
+data did not match any variant of untagged enum KeyAction
+"
+    );
+}
+
+#[test]
+fn test_find_config_file() {
+    let dir = Path::new(FORMAT_DIR);
+
+    assert_eq!(find_config_file(dir), dir.join("config.toml"));
+    assert_eq!(
+        find_config_file(&dir.join("missing")),
+        dir.join("missing/config.yaml")
+    );
+}
@@ -0,0 +1,74 @@
+use evdev::{EventType, InputEvent, RelativeAxisCode};
+
+use crate::{core::ControlCommand, daemon::Request};
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/layers.yaml");
+const POINTER_CONFIG: &str = include_str!("./config/pointer.yaml");
+
+#[test]
+fn test_control_layers() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    let state = adapter.control(ControlCommand::ActivateLayer("first_layer".into()));
+    assert_eq!(state.layers, ["first_layer"]);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_P)])?;
+
+    let state = adapter.control(ControlCommand::ToggleLayer("first_layer".into()));
+    assert!(state.layers.is_empty());
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_P)])?;
+
+    let state = adapter.control(ControlCommand::ToggleLayer("unknown_layer".into()));
+    assert!(state.layers.is_empty());
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_Q),
+        InputSequence::Tap(KeyCode::KEY_P),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_control_pause() {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    assert!(adapter.control(ControlCommand::Pause).paused);
+    assert!(!adapter.control(ControlCommand::Resume).paused);
+
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(POINTER_CONFIG, &mut proxy);
+    let key = InputEvent::new(EventType::KEY.0, KeyCode::KEY_A.0, 1);
+    let wheel = InputEvent::new(EventType::RELATIVE.0, RelativeAxisCode::REL_WHEEL.0, 1);
+
+    assert!(adapter.is_remapped_event(&key));
+    assert!(adapter.is_remapped_event(&wheel));
+
+    // the wheel events are passed through unchanged as well
+    adapter.control(ControlCommand::Pause);
+
+    assert!(!adapter.is_remapped_event(&key));
+    assert!(!adapter.is_remapped_event(&wheel));
+}
+
+#[test]
+fn test_control_request() {
+    let request = r#"{"command": "toggle_layer", "layer": "symbols"}"#;
+    let request = serde_json::from_str::<Request>(request).unwrap();
+
+    assert!(matches!(
+        request,
+        Request::ToggleLayer { layer, keyboard: None } if layer == "symbols"
+    ));
+
+    let request = serde_json::from_str::<Request>(r#"{"command": "reload"}"#).unwrap();
+
+    assert!(matches!(request, Request::Reload));
+}
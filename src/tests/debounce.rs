@@ -0,0 +1,66 @@
+use std::{thread, time::Duration};
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/debounce.yaml");
+
+#[test]
+fn test_debounce_chatter() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_A),
+        InputSequence::TapHold(KeyCode::KEY_A), // chatter
+        InputSequence::Tap(KeyCode::KEY_E),
+        InputSequence::Tap(KeyCode::KEY_E), // chatter
+    ])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_A),
+        InputSequence::Tap(KeyCode::KEY_R),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_debounce_delay() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_A)])?;
+    thread::sleep(Duration::from_millis(60));
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_A)])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_A),
+        InputSequence::Tap(KeyCode::KEY_A),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_debounce_key_override() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_B),
+        InputSequence::Tap(KeyCode::KEY_B),
+    ])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_B),
+        InputSequence::Tap(KeyCode::KEY_B),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use evdev::{BusType, InputId};
+
+use crate::{
+    config::schema::{Config, DeviceMatcher},
+    fs::device::{matches_device, DeviceInfo},
+};
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/device_match.yaml");
+
+fn parse_matcher() -> DeviceMatcher {
+    let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+    config.keyboards[0].matcher.as_ref().unwrap().matchers()[0].clone()
+}
+
+#[test]
+fn test_device_match_ids() {
+    let matcher = parse_matcher();
+
+    assert_eq!(matcher.vendor, Some(0x05ac));
+    assert_eq!(matcher.product, Some(0x024f));
+}
+
+#[test]
+fn test_device_match_glob() {
+    let matcher = parse_matcher();
+    let name = matcher.name.unwrap();
+    let path = matcher.path.unwrap();
+    let uniq = matcher.uniq.unwrap();
+
+    assert!(name.is_match("Keychron K2 Keyboard"));
+    assert!(name.is_match("Keychron K10 Keyboard"));
+    assert!(!name.is_match("Keychron Q1 Keyboard"));
+    assert!(!name.is_match("Keychron K2 Keyboard (virtual)"));
+    assert!(path.is_match("/dev/input/by-id/usb-Keychron_K2-event-kbd"));
+    assert!(!path.is_match("/dev/input/event3"));
+    assert!(uniq.is_match(""));
+}
+
+#[test]
+fn test_device_match_regex() {
+    let matcher = parse_matcher();
+    let phys = matcher.phys.unwrap();
+
+    assert!(phys.is_match("usb-0000:00:14.0-2/input0"));
+    assert!(!phys.is_match("usb-0000:00:14.0-3/input0"));
+    assert!(!phys.is_match("usb-0000:00:14x0-1/input0"));
+}
+
+#[test]
+fn test_device_match_multiple() {
+    let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+    let keyboard = &config.keyboards[1];
+    let matchers = keyboard.matcher.as_ref().unwrap().matchers();
+
+    assert!(keyboard.merge);
+    assert!(!config.keyboards[0].merge);
+    assert_eq!(matchers.len(), 2);
+    assert!(matchers[0]
+        .name
+        .as_ref()
+        .unwrap()
+        .is_match("Split Keyboard Left"));
+    assert_eq!(matchers[1].vendor, Some(0x1234));
+}
+
+#[test]
+fn test_device_match_virtual() {
+    let source = "keyboards: [{ match: { name: '*Keychron*' } }]";
+    let config: Config = serde_yaml::from_str(source).unwrap();
+
+    let device = |name: &str, id| DeviceInfo {
+        path: PathBuf::from("/dev/input/event3"),
+        aliases: Vec::new(),
+        name: Some(name.to_string()),
+        phys: None,
+        uniq: None,
+        id,
+    };
+
+    let virtual_device = device(
+        "Keychron K2 (virtual)",
+        InputId::new(BusType::BUS_VIRTUAL, 0x1234, 0x5678, 0),
+    );
+
+    let physical_device = device(
+        "Keychron K2",
+        InputId::new(BusType::BUS_USB, 0x05ac, 0x024f, 0),
+    );
+
+    assert!(!matches_device(&config.keyboards[0], &virtual_device));
+    assert!(matches_device(&config.keyboards[0], &physical_device));
+}
+
+#[test]
+fn test_device_match_invalid_regex() {
+    let source = "keyboards: [{ match: { name: { regex: '(' } } }]";
+
+    assert!(serde_yaml::from_str::<Config>(source).is_err());
+}
+
+#[test]
+fn test_device_match_adapter() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_CAPSLOCK)])?;
+
+    let expected = InputBuffer::new([InputSequence::Tap(KeyCode::KEY_ESC)]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::{io::Write, os::unix::net::UnixListener, thread};
+
+use crate::{
+    config::schema::{Config, FocusProvider},
+    daemon::{profile_layers, watch_focus, WindowInfo},
+};
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/app_profiles.yaml");
+
+fn window(class: &str, title: &str) -> WindowInfo {
+    WindowInfo {
+        class: class.to_string(),
+        title: title.to_string(),
+    }
+}
+
+#[test]
+fn test_profile_layers() {
+    let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+    let profiles = config.app_profiles.unwrap().profiles;
+
+    assert_eq!(
+        profile_layers(&profiles, &window("firefox", "")),
+        ["vim_nav"]
+    );
+    assert_eq!(
+        profile_layers(&profiles, &window("gamescope", "")),
+        ["gaming"]
+    );
+    assert_eq!(
+        profile_layers(&profiles, &window("firefox-esr", "")),
+        [""; 0]
+    );
+    assert_eq!(profile_layers(&profiles, &window("xterm", "bash")), [""; 0]);
+
+    assert_eq!(
+        profile_layers(&profiles, &window("xterm", "nvim config.yaml")),
+        ["vim_nav", "editor"]
+    );
+}
+
+#[test]
+fn test_socket_provider() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("okey-focus-{}.sock", std::process::id()));
+    let listener = UnixListener::bind(&path)?;
+
+    // stand-in for a window manager script
+    let server = thread::spawn(move || -> Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        writeln!(stream, "firefox")?;
+        writeln!(stream, r#"{{"class": "kitty", "title": "vim"}}"#)?;
+        Ok(())
+    });
+
+    let mut windows = Vec::new();
+    let provider = FocusProvider::Socket {
+        socket: path.clone(),
+    };
+    let result = watch_focus(&provider, |window| {
+        windows.push(window);
+        Ok(())
+    });
+
+    server.join().unwrap()?;
+    std::fs::remove_file(path)?;
+
+    assert!(result.is_err()); // the connection has been closed
+    assert_eq!(windows, [window("firefox", ""), window("kitty", "vim")]);
+
+    Ok(())
+}
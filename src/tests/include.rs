@@ -0,0 +1,138 @@
+use std::fs;
+
+use super::utils::*;
+
+use crate::{
+    config::lint::{lint_config, Lint},
+    fs::config::{parse_config, read_config, read_config_with_files, ConfigFormat},
+};
+
+const CONFIG_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/config");
+
+fn config_path(name: &str) -> Option<String> {
+    Some(format!("{CONFIG_DIR}/{name}"))
+}
+
+#[test]
+fn test_includes_and_templates() -> Result<()> {
+    let mut config = read_config(config_path("includes.yaml"))?;
+
+    assert_eq!(config.keyboards.len(), 2);
+    assert_eq!(config.defaults.general.deferred_key_delay, 5);
+    assert!(lint_config(&config).is_empty());
+
+    let keyboard = config.keyboards.remove(0);
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::new(keyboard, config.defaults.clone(), &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_A),         // keyboard
+        InputSequence::Tap(KeyCode::KEY_Z),         // keyboard include
+        InputSequence::Tap(KeyCode::KEY_Q),         // nested template
+        InputSequence::Hold(KeyCode::KEY_CAPSLOCK), // template include
+        InputSequence::Tap(KeyCode::KEY_H),
+        InputSequence::Release(KeyCode::KEY_CAPSLOCK),
+        InputSequence::Hold(KeyCode::KEY_RIGHTALT),
+        InputSequence::Tap(KeyCode::KEY_J),
+        InputSequence::Release(KeyCode::KEY_RIGHTALT),
+    ])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_B),
+        InputSequence::Tap(KeyCode::KEY_X),
+        InputSequence::Tap(KeyCode::KEY_W),
+        InputSequence::Tap(KeyCode::KEY_LEFT),
+        InputSequence::Tap(KeyCode::KEY_1),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    let template_keyboard = &config.keyboards[0];
+
+    assert!(template_keyboard.layers.contains_key("navigation"));
+    assert!(!template_keyboard.layers.contains_key("symbols"));
+
+    Ok(())
+}
+
+#[test]
+fn test_included_files() -> Result<()> {
+    let (_, files) = read_config_with_files(config_path("includes.yaml"))?;
+    let dir = fs::canonicalize(CONFIG_DIR)?;
+
+    let expected = [
+        "include/common.yaml",
+        "include/navigation.yaml",
+        "include/symbols.yaml",
+        "includes.yaml",
+    ];
+
+    assert_eq!(files, expected.map(|file| dir.join(file)));
+
+    Ok(())
+}
+
+#[test]
+fn test_include_cycle() {
+    let error = read_config(config_path("include/include_cycle.yaml"))
+        .unwrap_err()
+        .to_string();
+
+    let dir = fs::canonicalize(CONFIG_DIR).unwrap().join("include");
+    let config = dir.join("include_cycle.yaml");
+    let fragment = dir.join("include_cycle_fragment.yaml");
+
+    assert_eq!(
+        error,
+        format!(
+            "Include cycle detected: {} -> {} -> {}",
+            config.display(),
+            fragment.display(),
+            config.display()
+        )
+    );
+}
+
+#[test]
+fn test_include_error_origin() {
+    let error = read_config(config_path("include/invalid_include.yaml"))
+        .unwrap_err()
+        .to_string();
+
+    let dir = fs::canonicalize(CONFIG_DIR).unwrap().join("include");
+    let fragment = dir.join("invalid_include_fragment.yaml");
+    let prefix = format!("{}: keyboards[0].keys.KEY_Q: ", fragment.display());
+
+    assert!(error.starts_with(&prefix), "{error}");
+}
+
+#[test]
+fn test_template_cycle() {
+    let error = read_config(config_path("include/template_cycle.yaml"))
+        .unwrap_err()
+        .to_string();
+
+    assert_eq!(error, "Template cycle detected: first -> second -> first");
+}
+
+#[test]
+fn test_unused_template() {
+    let config = r#"
+templates:
+  unused:
+    keys:
+      KEY_Q: KEY_W
+
+keyboards: []
+"#;
+
+    let config = parse_config(config, ConfigFormat::Yaml).unwrap();
+
+    assert_eq!(
+        lint_config(&config),
+        [Lint {
+            keyboard: "template unused".to_string(),
+            message: "Template is never extended".to_string(),
+        }]
+    );
+}
@@ -0,0 +1,47 @@
+use std::time::{Duration, Instant};
+
+use evdev::LedCode;
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/indicators.yaml");
+
+#[test]
+fn test_layer_indicator() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    assert!(adapter.indicator_states(Instant::now()).is_empty());
+
+    adapter.process_sequence([InputSequence::Hold(KeyCode::KEY_SPACE)])?;
+
+    let states = adapter.indicator_states(Instant::now());
+    assert_eq!(states, [(LedCode::LED_SCROLLL, true)]);
+
+    adapter.process_sequence([InputSequence::Release(KeyCode::KEY_SPACE)])?;
+
+    assert!(adapter.indicator_states(Instant::now()).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_blinking_indicator() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_F12)])?;
+
+    let now = Instant::now();
+    let states = adapter.indicator_states(now);
+    assert_eq!(states, [(LedCode::LED_CAPSL, true)]);
+
+    let states = adapter.indicator_states(now + Duration::from_millis(1500));
+    assert_eq!(states, [(LedCode::LED_CAPSL, false)]);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_F12)])?;
+
+    assert!(adapter.indicator_states(Instant::now()).is_empty());
+
+    Ok(())
+}
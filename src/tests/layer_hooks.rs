@@ -0,0 +1,52 @@
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/layer_hooks.yaml");
+
+#[test]
+fn test_layer_enter_exit() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Hold(KeyCode::KEY_CAPSLOCK),
+        InputSequence::Tap(KeyCode::KEY_J),
+        InputSequence::Release(KeyCode::KEY_CAPSLOCK),
+    ])?;
+
+    adapter.wait_macros()?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_F13),
+        InputSequence::Tap(KeyCode::KEY_DOWN),
+        InputSequence::Tap(KeyCode::KEY_F14),
+        InputSequence::Tap(KeyCode::KEY_F15),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_layer_toggle_exit() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    // only the exit action is defined
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_F12),
+        InputSequence::Tap(KeyCode::KEY_F12),
+    ])?;
+
+    adapter.wait_macros()?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_B),
+        InputSequence::Tap(KeyCode::KEY_Y),
+        InputSequence::Tap(KeyCode::KEY_E),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use super::utils::*;
+
+const FR_CONFIG: &str = include_str!("./config/layout_fr.yaml");
+const DE_CONFIG: &str = include_str!("./config/layout_de.yaml");
+
+#[test]
+fn test_azerty_string_macro() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(FR_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_Q)])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Shifted(KeyCode::KEY_Q),
+        InputSequence::Tap(KeyCode::KEY_2),
+        InputSequence::Shifted(KeyCode::KEY_1),
+        InputSequence::AltGr(KeyCode::KEY_0),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_unmapped_char_fallback() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(FR_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_W)])?;
+
+    // hex digits are typed using the layout too
+    let expected = InputBuffer::new([
+        InputSequence::Unicode,
+        InputSequence::Tap(KeyCode::KEY_F),
+        InputSequence::Shifted(KeyCode::KEY_4),
+        InputSequence::Tap(KeyCode::KEY_ENTER),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    proxy.clear();
+
+    let mut adapter = KeyAdapter::with_config(FR_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_E)])?;
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_qwertz_string_macro() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(DE_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_Q)])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_Y),
+        InputSequence::Shifted(KeyCode::KEY_Z),
+        InputSequence::Tap(KeyCode::KEY_MINUS),
+        InputSequence::AltGr(KeyCode::KEY_Q),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::{
+    config::{
+        lint::{group_lints, lint_config, suggest_keycode, Lint},
+        schema::known_keycodes,
+    },
+    fs::config::{parse_config, ConfigFormat},
+};
+
+const CONFIG: &str = include_str!("./config/lints.yaml");
+
+#[test]
+fn test_lints() {
+    let config = parse_config(CONFIG, ConfigFormat::Yaml).unwrap();
+    let lints = lint_config(&config);
+
+    let messages = lints
+        .iter()
+        .map(|lint| lint.message.as_str())
+        .collect::<Vec<_>>();
+
+    assert!(lints
+        .iter()
+        .all(|lint| lint.keyboard == "Lint test keyboard"));
+
+    assert_eq!(
+        messages,
+        [
+            "Unknown keycode KEY_ESCAPE (did you mean KEY_ESC?), declare it in custom_keycodes if it is a custom keycode",
+            "Custom keycode KEY_ESCAPE is never bound to a layer, a tap dance or a mapping",
+            "Layer symbols is unreachable, its modifier LAYER_SYMBOLS is never emitted",
+            "Layers function and numbers share the modifier KEY_SPACE, only one of them is used",
+            "Combo [KEY_S, KEY_D] overlaps with the tap dance on KEY_S",
+            "Combo [KEY_K, KEY_J] is defined more than once",
+        ]
+    );
+}
+
+#[test]
+fn test_parse_error_path() {
+    let config = r#"
+keyboards:
+  - name: "Invalid keyboard"
+    tap_dances:
+      KEY_A:
+        tap: KEY_A
+        hold: { delay: "long" }
+"#;
+
+    let error = parse_config(config, ConfigFormat::Yaml)
+        .unwrap_err()
+        .to_string();
+
+    assert_eq!(
+        error,
+        "keyboards[0].tap_dances.KEY_A.hold: data did not match any variant of untagged enum KeyAction at line 6 column 9"
+    );
+}
+
+#[test]
+fn test_keycode_suggestions() {
+    let known = known_keycodes();
+
+    assert_eq!(suggest_keycode("KEY_ESCAPE", &known), Some("KEY_ESC"));
+    assert_eq!(suggest_keycode("key_enter", &known), Some("KEY_ENTER"));
+    assert_eq!(
+        suggest_keycode("KEY_BACKSPCE", &known),
+        Some("KEY_BACKSPACE")
+    );
+    assert_eq!(
+        suggest_keycode("KEY_EXCLAMATON", &known),
+        Some("KEY_EXCLAMATION")
+    );
+    assert_eq!(suggest_keycode("WHEEL_UPP", &known), Some("WHEEL_UP"));
+    assert_eq!(suggest_keycode("MY_CUSTOM_ACTION", &known), None);
+}
+
+#[test]
+fn test_group_lints() {
+    let lint = |keyboard: &str, message: &str| Lint {
+        keyboard: keyboard.to_string(),
+        message: message.to_string(),
+    };
+
+    // device lints are appended after the configuration ones
+    let lints = [
+        lint("First keyboard", "Layer unused is never activated"),
+        lint("Second keyboard", "Key KEY_A is mapped to itself"),
+        lint(
+            "First keyboard",
+            "No matching device is currently connected",
+        ),
+    ];
+
+    assert_eq!(
+        group_lints(&lints),
+        [
+            (
+                "First keyboard",
+                vec![
+                    "Layer unused is never activated",
+                    "No matching device is currently connected"
+                ]
+            ),
+            ("Second keyboard", vec!["Key KEY_A is mapped to itself"]),
+        ]
+    );
+}
@@ -1,3 +1,10 @@
+use std::{
+    env, fs,
+    path::Path,
+    process, thread,
+    time::{Duration, Instant},
+};
+
 use super::utils::*;
 
 const CONFIG: &str = include_str!("./config/macros.yaml");
@@ -49,6 +56,7 @@ fn test_event_macro() -> Result<()> {
     let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
 
     adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_X)])?;
+    adapter.wait_macros()?;
 
     let expected = InputBuffer::new([
         InputSequence::Shifted(KeyCode::KEY_O),
@@ -109,6 +117,7 @@ fn test_unicode_macro() -> Result<()> {
     let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
 
     adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_T)])?;
+    adapter.wait_macros()?;
 
     let expected = InputBuffer::new([
         InputSequence::Unicode,
@@ -138,6 +147,7 @@ fn test_shell_macro() -> Result<()> {
     let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
 
     adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_Z)])?;
+    adapter.wait_macros()?;
 
     let expected = InputBuffer::new([
         InputSequence::Tap(KeyCode::KEY_F),
@@ -149,3 +159,261 @@ fn test_shell_macro() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_macro_delay_non_blocking() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_X),
+        InputSequence::Tap(KeyCode::KEY_J),
+    ])?;
+
+    adapter.wait_macros()?;
+
+    // keys pressed during the delay should be processed immediately
+    let expected = InputBuffer::new([
+        InputSequence::Shifted(KeyCode::KEY_O),
+        InputSequence::Tap(KeyCode::KEY_J),
+        InputSequence::Tap(KeyCode::KEY_K),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_macro_repeat() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_A)])?;
+    adapter.wait_macros()?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_B),
+        InputSequence::Tap(KeyCode::KEY_B),
+        InputSequence::Tap(KeyCode::KEY_B),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_macro_cancel_key() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_S)])?;
+
+    assert!(adapter.has_running_macros());
+
+    adapter.process_sequence([InputSequence::Press(KeyCode::KEY_ESC)])?;
+
+    assert!(!adapter.has_running_macros());
+
+    // the cancel key should only be consumed when a macro was running
+    adapter.process_sequence([
+        InputSequence::Release(KeyCode::KEY_ESC),
+        InputSequence::Tap(KeyCode::KEY_ESC),
+    ])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_C),
+        InputSequence::Tap(KeyCode::KEY_ESC),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_macro_cancel_trigger() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_S)])?;
+
+    assert!(adapter.has_running_macros());
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_S)])?;
+
+    assert!(!adapter.has_running_macros());
+
+    let expected = InputBuffer::new([InputSequence::Tap(KeyCode::KEY_C)]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_macro_cancel_release() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_F)])?;
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_ESC)])?;
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_G)])?;
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_G)])?;
+
+    assert!(!adapter.has_running_macros());
+
+    // the keys pressed by the cancelled macros should be released
+    let expected = InputBuffer::new([
+        InputSequence::Press(KeyCode::BTN_LEFT),
+        InputSequence::Release(KeyCode::BTN_LEFT),
+        InputSequence::Hold(KeyCode::KEY_LEFTSHIFT),
+        InputSequence::Release(KeyCode::KEY_LEFTSHIFT),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_macro_shared_by_keys() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_S)])?;
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_D)])?;
+
+    // the same macro bound to another key should not cancel the first one
+    assert!(adapter.has_running_macros());
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_S)])?;
+
+    assert!(adapter.has_running_macros());
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_D)])?;
+
+    assert!(!adapter.has_running_macros());
+
+    Ok(())
+}
+
+#[test]
+fn test_shell_macro_env() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_1)])?;
+    adapter.wait_macros()?;
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_2)])?;
+    adapter.wait_macros()?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_B),
+        InputSequence::Tap(KeyCode::KEY_A),
+        InputSequence::Tap(KeyCode::KEY_R),
+        InputSequence::Tap(KeyCode::KEY_SLASH),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_shell_macro_error() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_3),
+        InputSequence::Tap(KeyCode::KEY_4),
+    ])?;
+
+    adapter.wait_macros()?;
+
+    // stderr is typed instead of stdout
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_B),
+        InputSequence::Tap(KeyCode::KEY_A),
+        InputSequence::Tap(KeyCode::KEY_R),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_shell_macro_timeout() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+    let start = Instant::now();
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_5)])?;
+    adapter.wait_macros()?;
+
+    assert!(start.elapsed().as_secs() < 5);
+    assert!(proxy.queue().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_shell_macro_background_timeout() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+    let start = Instant::now();
+
+    // the background process keeps stdout open after the shell exits
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_8)])?;
+    adapter.wait_macros()?;
+
+    assert!(start.elapsed().as_secs() < 5);
+    assert!(proxy.queue().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_exec_macro() -> Result<()> {
+    // the output directory is unique to this process to keep concurrent runs apart
+    let dir = env::temp_dir().join(format!("okey_exec_{}", process::id()));
+    let config = CONFIG.replace("${TMPDIR:-/tmp}", &dir.to_string_lossy());
+    let path = dir.join("okey_exec");
+    let detached_path = dir.join("okey_exec_detach");
+
+    fs::create_dir_all(&dir)?;
+
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(&config, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_6),
+        InputSequence::Tap(KeyCode::KEY_7),
+    ])?;
+
+    // commands should not block nor type anything
+    assert!(!adapter.has_running_macros());
+    assert!(proxy.queue().is_empty());
+
+    wait_for_file(&path);
+    wait_for_file(&detached_path);
+
+    assert_eq!(fs::read_to_string(&path)?, "foo\n");
+    assert_eq!(fs::read_to_string(&detached_path)?, "/\n");
+
+    fs::remove_dir_all(&dir)?;
+
+    Ok(())
+}
+
+fn wait_for_file(path: &Path) {
+    let start = Instant::now();
+
+    while fs::read_to_string(path).map_or(true, |content| content.is_empty()) {
+        assert!(start.elapsed().as_secs() < 5, "{path:?} was not written");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -1,8 +1,24 @@
 mod combo;
+mod conditions;
+mod config_format;
+mod control;
+mod debounce;
+mod device;
+mod focus;
+mod include;
+mod indicator;
+mod layer_hooks;
 mod layers;
+mod layout;
+mod lint;
 mod macros;
 mod mapping;
+mod pointer;
+mod reload;
+mod repeat;
 mod schema;
 mod shift;
 mod tap_dance;
+mod unicode;
 mod utils;
+mod watch;
@@ -0,0 +1,86 @@
+use evdev::{EventType, InputEvent, RelativeAxisCode};
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/pointer.yaml");
+
+fn wheel(axis: RelativeAxisCode, value: i32) -> InputEvent {
+    InputEvent::new(EventType::RELATIVE.0, axis.0, value)
+}
+
+#[test]
+fn test_mouse_button() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::BTN_SIDE)])?;
+
+    let expected = InputBuffer::new(
+        [InputSequence::Tap(KeyCode::KEY_BACK)], //
+    );
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_wheel_to_key() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_wheel_event(wheel(RelativeAxisCode::REL_WHEEL, 2))?;
+    adapter.process_wheel_event(wheel(RelativeAxisCode::REL_WHEEL_HI_RES, 240))?;
+    adapter.post_process()?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_VOLUMEUP),
+        InputSequence::Tap(KeyCode::KEY_VOLUMEUP),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_wheel_layer() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Press(KeyCode::BTN_EXTRA)])?;
+    adapter.process_wheel_event(wheel(RelativeAxisCode::REL_WHEEL, -1))?;
+    adapter.process_sequence([InputSequence::Release(KeyCode::BTN_EXTRA)])?;
+
+    let expected = InputBuffer::new(
+        [InputSequence::Tap(KeyCode::KEY_KPMINUS)], //
+    );
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_key_to_wheel() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([
+        InputSequence::TapHold(KeyCode::KEY_PAGEDOWN),
+        InputSequence::Tap(KeyCode::KEY_PAGEUP),
+    ])?;
+
+    let expected = [
+        wheel(RelativeAxisCode::REL_WHEEL, -1),
+        wheel(RelativeAxisCode::REL_WHEEL_HI_RES, -120),
+        wheel(RelativeAxisCode::REL_WHEEL, -1),
+        wheel(RelativeAxisCode::REL_WHEEL_HI_RES, -120),
+        wheel(RelativeAxisCode::REL_HWHEEL, 1),
+        wheel(RelativeAxisCode::REL_HWHEEL_HI_RES, 120),
+    ];
+
+    assert_eq!(proxy.queue(), expected);
+
+    Ok(())
+}
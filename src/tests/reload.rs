@@ -0,0 +1,29 @@
+use super::utils::*;
+
+const MAPPING_CONFIG: &str = include_str!("./config/mappings.yaml");
+const LAYER_CONFIG: &str = include_str!("./config/layers.yaml");
+
+#[test]
+fn test_reload_config() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(MAPPING_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_Q)])?;
+    adapter.reload_config(LAYER_CONFIG);
+    adapter.process_sequence([
+        InputSequence::Tap(KeyCode::KEY_Q),
+        InputSequence::Hold(KeyCode::KEY_SPACE), // first layer
+        InputSequence::Tap(KeyCode::KEY_P),
+        InputSequence::Release(KeyCode::KEY_SPACE),
+    ])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_W),
+        InputSequence::Tap(KeyCode::KEY_Q),
+        InputSequence::Tap(KeyCode::KEY_Q),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
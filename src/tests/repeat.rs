@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/repeat.yaml");
+
+#[test]
+fn test_key_repeat() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Hold(KeyCode::KEY_A)])?; // kernel repeat is ignored
+
+    let now = Instant::now();
+    adapter.process_repeat(now + Duration::from_millis(50))?;
+    adapter.process_repeat(now + Duration::from_millis(150))?;
+    adapter.process_repeat(now + Duration::from_millis(150))?;
+    adapter.process_sequence([InputSequence::Release(KeyCode::KEY_A)])?;
+    adapter.process_repeat(now + Duration::from_millis(300))?;
+
+    let expected = InputBuffer::new(
+        [InputSequence::TapHold(KeyCode::KEY_B)], //
+    );
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_disabled_key_repeat() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Hold(KeyCode::KEY_Q)])?;
+    adapter.process_repeat(Instant::now() + Duration::from_millis(150))?;
+    adapter.process_sequence([InputSequence::Release(KeyCode::KEY_Q)])?;
+
+    let expected = InputBuffer::new(
+        [InputSequence::Tap(KeyCode::KEY_W)], // the remapped key is disabled
+    );
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_key_repeat_override() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Press(KeyCode::KEY_E)])?;
+
+    let now = Instant::now();
+    adapter.process_repeat(now + Duration::from_millis(150))?;
+    adapter.process_repeat(now + Duration::from_millis(350))?;
+    adapter.process_sequence([InputSequence::Release(KeyCode::KEY_E)])?;
+
+    let expected = InputBuffer::new(
+        [InputSequence::TapHold(KeyCode::KEY_E)], //
+    );
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
@@ -1,13 +1,32 @@
 use jsonschema::draft7 as jsonschema;
 
+use crate::config::schema::config_schema;
+
 const SCHEMA: &str = include_str!("../../schema/okey.json");
 
 const TD_TEST: &str = include_str!("./config/tap_dances.yaml");
 const COMBO_TEST: &str = include_str!("./config/combos.yaml");
 const LAYER_TEST: &str = include_str!("./config/layers.yaml");
+const DEVICE_TEST: &str = include_str!("./config/device_match.yaml");
+const LAYOUT_TEST: &str = include_str!("./config/layout_fr.yaml");
+const COMPOSE_TEST: &str = include_str!("./config/unicode_compose.yaml");
+const COMPOSE_DE_TEST: &str = include_str!("./config/unicode_compose_de.yaml");
+const CLIPBOARD_TEST: &str = include_str!("./config/unicode_clipboard.yaml");
+const COMMAND_TEST: &str = include_str!("./config/unicode_command.yaml");
 const MACRO_TEST: &str = include_str!("./config/macros.yaml");
 const MAPPING_TEST: &str = include_str!("./config/mappings.yaml");
 const SHIFT_TEST: &str = include_str!("./config/shift.yaml");
+const POINTER_TEST: &str = include_str!("./config/pointer.yaml");
+const INDICATOR_TEST: &str = include_str!("./config/indicators.yaml");
+const DEBOUNCE_TEST: &str = include_str!("./config/debounce.yaml");
+const REPEAT_TEST: &str = include_str!("./config/repeat.yaml");
+const LAYER_HOOK_TEST: &str = include_str!("./config/layer_hooks.yaml");
+const APP_PROFILE_TEST: &str = include_str!("./config/app_profiles.yaml");
+const CONDITION_TEST: &str = include_str!("./config/conditions.yaml");
+const LINT_TEST: &str = include_str!("./config/lints.yaml");
+const INCLUDE_TEST: &str = include_str!("./config/includes.yaml");
+const TOML_TEST: &str = include_str!("./config/formats/config.toml");
+const JSON_TEST: &str = include_str!("./config/formats/config.json");
 
 const TD_EX: &str = include_str!("../../examples/tap_dance_hrm.yaml");
 const COMBO_EX: &str = include_str!("../../examples/combo_hrm.yaml");
@@ -26,8 +45,37 @@ fn test_config_validation() {
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(TD_TEST)));
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(COMBO_TEST)));
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(LAYER_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(DEVICE_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(LAYOUT_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(COMPOSE_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(COMPOSE_DE_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(CLIPBOARD_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(COMMAND_TEST)));
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(MACRO_TEST)));
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(MAPPING_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(POINTER_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(INDICATOR_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(DEBOUNCE_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(REPEAT_TEST)));
+    assert!(jsonschema::is_valid(
+        &schema,
+        &yaml_to_json(LAYER_HOOK_TEST)
+    ));
+    assert!(jsonschema::is_valid(
+        &schema,
+        &yaml_to_json(APP_PROFILE_TEST)
+    ));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(CONDITION_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(LINT_TEST)));
+    assert!(jsonschema::is_valid(&schema, &yaml_to_json(INCLUDE_TEST)));
+    assert!(jsonschema::is_valid(
+        &schema,
+        &toml::from_str(TOML_TEST).unwrap()
+    ));
+    assert!(jsonschema::is_valid(
+        &schema,
+        &serde_json::from_str(JSON_TEST).unwrap()
+    ));
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(SHIFT_TEST)));
 
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(TD_EX)));
@@ -36,3 +84,14 @@ fn test_config_validation() {
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(MACRO_EX)));
     assert!(jsonschema::is_valid(&schema, &yaml_to_json(MAPPING_EX)));
 }
+
+#[test]
+fn test_generated_schema() {
+    let schema = serde_json::from_str::<serde_json::Value>(SCHEMA).unwrap();
+
+    assert_eq!(
+        config_schema().to_value(),
+        schema,
+        "schema/okey.json is outdated, run `okey schema > schema/okey.json`"
+    );
+}
@@ -0,0 +1,117 @@
+use std::{env, fs, process};
+
+use super::utils::*;
+
+const COMPOSE_CONFIG: &str = include_str!("./config/unicode_compose.yaml");
+const CLIPBOARD_CONFIG: &str = include_str!("./config/unicode_clipboard.yaml");
+const COMPOSE_DE_CONFIG: &str = include_str!("./config/unicode_compose_de.yaml");
+const COMMAND_CONFIG: &str = include_str!("./config/unicode_command.yaml");
+
+#[test]
+fn test_compose_unicode() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(COMPOSE_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_Q)])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Tap(KeyCode::KEY_COMPOSE),
+        InputSequence::Tap(KeyCode::KEY_APOSTROPHE),
+        InputSequence::Tap(KeyCode::KEY_E),
+        InputSequence::Tap(KeyCode::KEY_COMPOSE),
+        InputSequence::Tap(KeyCode::KEY_S),
+        InputSequence::Tap(KeyCode::KEY_S),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_compose_unicode_fallback() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(COMPOSE_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_W)])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Unicode,
+        InputSequence::Tap(KeyCode::KEY_1),
+        InputSequence::Tap(KeyCode::KEY_F),
+        InputSequence::Tap(KeyCode::KEY_6),
+        InputSequence::Tap(KeyCode::KEY_4),
+        InputSequence::Tap(KeyCode::KEY_2),
+        InputSequence::Tap(KeyCode::KEY_ENTER),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_compose_unicode_missing_dead_key() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(COMPOSE_DE_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_Q)])?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Unicode,
+        InputSequence::Tap(KeyCode::KEY_E),
+        InputSequence::Tap(KeyCode::KEY_8),
+        InputSequence::Tap(KeyCode::KEY_ENTER),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_clipboard_unicode() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CLIPBOARD_CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_Q)])?;
+    adapter.wait_macros()?;
+
+    let expected = InputBuffer::new([
+        InputSequence::Hold(KeyCode::KEY_LEFTCTRL),
+        InputSequence::Hold(KeyCode::KEY_LEFTSHIFT),
+        InputSequence::Tap(KeyCode::KEY_V),
+        InputSequence::Release(KeyCode::KEY_LEFTSHIFT),
+        InputSequence::Release(KeyCode::KEY_LEFTCTRL),
+    ]);
+
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
+
+#[test]
+fn test_command_unicode() -> Result<()> {
+    // the output file is unique to this process to keep concurrent runs apart
+    let path = env::temp_dir().join(format!("okey_unicode_command_{}", process::id()));
+    let config = COMMAND_CONFIG.replace(
+        "${TMPDIR:-/tmp}/okey_unicode_command",
+        &path.to_string_lossy(),
+    );
+
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(&config, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Tap(KeyCode::KEY_Q)])?;
+    adapter.wait_macros()?;
+
+    let output = fs::read_to_string(&path)?;
+    let expected = InputBuffer::new([InputSequence::Tap(KeyCode::KEY_A)]);
+
+    fs::remove_file(&path)?;
+
+    assert_eq!(output, "🙂");
+    assert_eq!(proxy.queue(), expected.value());
+
+    Ok(())
+}
@@ -48,6 +48,10 @@ impl EventProxyMock {
     pub fn queue(&self) -> &[InputEvent] {
         &self.queue
     }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
 }
 
 impl EventProxy for EventProxyMock {
@@ -70,6 +74,22 @@ impl<'a, P: EventProxy> KeyAdapter<'a, P> {
 
         KeyAdapter::new(keyboard, defaults, proxy)
     }
+
+    pub fn reload_config(&mut self, config: &str) {
+        let mut config: Config = serde_yaml::from_str(config).unwrap();
+        let keyboard = config.keyboards.remove(0);
+
+        self.reload(keyboard, config.defaults);
+    }
+
+    pub fn wait_macros(&mut self) -> Result<()> {
+        while self.has_running_macros() {
+            thread::sleep(Duration::from_millis(1));
+            self.post_process()?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -101,6 +121,7 @@ pub enum InputSequence {
     Hold(KeyCode),
     Release(KeyCode),
     Shifted(KeyCode),
+    AltGr(KeyCode),
     Tap(KeyCode),
     TapHold(KeyCode),
     ComboPress(Vec<KeyCode>),
@@ -118,9 +139,10 @@ impl InputSequence {
             InputSequence::Tap(code) => vec![press(code), release(code)],
             InputSequence::TapHold(code) => vec![press(code), hold(code), release(code)],
             InputSequence::Shifted(code) => Self::shifted(code),
-            InputSequence::ComboPress(keys) => keys.into_iter().map(|k| press(k)).collect(),
-            InputSequence::ComboHold(keys) => keys.into_iter().map(|k| hold(k)).collect(),
-            InputSequence::ComboRelease(keys) => keys.into_iter().map(|k| release(k)).collect(),
+            InputSequence::AltGr(code) => Self::altgr(code),
+            InputSequence::ComboPress(keys) => keys.into_iter().map(press).collect(),
+            InputSequence::ComboHold(keys) => keys.into_iter().map(hold).collect(),
+            InputSequence::ComboRelease(keys) => keys.into_iter().map(release).collect(),
             InputSequence::Unicode => Self::unicode(),
         }
     }
@@ -135,6 +157,16 @@ impl InputSequence {
         ]
     }
 
+    fn altgr(code: KeyCode) -> Vec<InputEvent> {
+        vec![
+            press(KeyCode::KEY_RIGHTALT),
+            hold(KeyCode::KEY_RIGHTALT),
+            press(code),
+            release(code),
+            release(KeyCode::KEY_RIGHTALT),
+        ]
+    }
+
     fn unicode() -> Vec<InputEvent> {
         vec![
             press(KeyCode::KEY_LEFTCTRL),
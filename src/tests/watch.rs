@@ -0,0 +1,84 @@
+use crate::{
+    cli::{commands::watch::format_state, WatchFormat},
+    core::ControlCommand,
+    daemon::KeyboardState,
+};
+
+use super::utils::*;
+
+const CONFIG: &str = include_str!("./config/layers.yaml");
+
+#[test]
+fn test_layer_state() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    adapter.process_sequence([InputSequence::Hold(KeyCode::KEY_SPACE)])?;
+    assert_eq!(adapter.state().layers, ["first_layer"]);
+
+    adapter.process_sequence([InputSequence::Release(KeyCode::KEY_SPACE)])?;
+    assert!(adapter.state().layers.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_layer_state_change() -> Result<()> {
+    let mut proxy = EventProxyMock::default();
+    let mut adapter = KeyAdapter::with_config(CONFIG, &mut proxy);
+
+    assert!(adapter.take_state_change().is_some());
+    assert!(adapter.take_state_change().is_none());
+
+    adapter.process_sequence([InputSequence::Hold(KeyCode::KEY_SPACE)])?;
+    let state = adapter.take_state_change().map(|state| state.layers);
+    assert_eq!(state, Some(vec!["first_layer".to_string()]));
+    assert!(adapter.take_state_change().is_none());
+
+    adapter.process_sequence([InputSequence::Release(KeyCode::KEY_SPACE)])?;
+    let state = adapter.take_state_change().map(|state| state.layers);
+    assert_eq!(state, Some(vec![]));
+
+    adapter.control(ControlCommand::Pause);
+    assert!(adapter
+        .take_state_change()
+        .is_some_and(|state| state.paused));
+
+    adapter.control(ControlCommand::Pause);
+    assert!(adapter.take_state_change().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_watch_format() {
+    let mut state = KeyboardState {
+        name: "Main keyboard".to_string(),
+        devices: vec!["/dev/input/event0".into()],
+        layers: vec!["symbols".to_string(), "nav".to_string()],
+        paused: false,
+    };
+
+    assert_eq!(
+        format_state(&state, WatchFormat::Text),
+        "Main keyboard: symbols > nav"
+    );
+
+    assert_eq!(
+        format_state(&state, WatchFormat::Json),
+        r#"{"devices":["/dev/input/event0"],"layers":["symbols","nav"],"name":"Main keyboard","paused":false}"#
+    );
+
+    state.layers.clear();
+    state.paused = true;
+
+    assert_eq!(
+        format_state(&state, WatchFormat::Text),
+        "Main keyboard: base (paused)"
+    );
+
+    assert_eq!(
+        format_state(&state, WatchFormat::Waybar),
+        r#"{"alt":"base","class":"paused","text":"base","tooltip":"Main keyboard: base (paused)"}"#
+    );
+}